@@ -1,11 +1,26 @@
 #version 450
 
 layout(location = 0) in vec2 v_uv_out;
+layout(location = 1) flat in vec2 v_tile_out;
+// interpolated between the corners so the shadow fades out across the face
+layout(location = 2) in float v_ao_out;
+// red, green and blue, multiplied with the texture so coloured light tints it
+layout(location = 3) in vec3 v_light_out;
 
 layout(location = 0) out vec4 out_color;
 
 layout(binding = 1) uniform sampler2D tex;
 
+// the atlas is 10 by 10 tiles, the same as ATLAS_TILES in mesher.rs
+const float TILE_SIZE = 0.1;
+// texels less opaque than this get thrown away, the translucent pipeline sets it to 0 and blends them instead
+layout(constant_id = 0) const float ALPHA_CUTOFF = 0.5;
+
 void main() {
-    out_color = texture(tex, v_uv_out);
+    // wraps around inside of the tile so merged quads repeat the texture instead of stretching it
+    vec4 color = texture(tex, v_tile_out + fract(v_uv_out) * TILE_SIZE);
+    if (color.a < ALPHA_CUTOFF) {
+        discard;
+    }
+    out_color = vec4(color.rgb * v_ao_out * v_light_out, color.a);
 }
@@ -1,9 +1,12 @@
 #version 450
 
-layout(location = 0) in vec3 v_pos;
-layout(location = 1) in vec2 v_uv;
+// a ChunkVertex from vertex.rs
+layout(location = 0) in uvec2 v_data;
 
 layout(location = 0) out vec2 v_uv_out;
+layout(location = 1) flat out vec2 v_tile_out;
+layout(location = 2) out float v_ao_out;
+layout(location = 3) out vec3 v_light_out;
 
 layout(binding = 0) uniform Camera {
     mat4 proj;
@@ -14,7 +17,31 @@ layout(binding = 2) uniform Model {
     mat4 model;
 };
 
+// the atlas is 10 by 10 tiles, the same as ATLAS_TILES in mesher.rs
+const uint ATLAS_TILES = 10;
+// how bright each ao level is, from fully in a corner to not occluded at all (ChunkVertex::MAX_AO)
+const float AO_BRIGHTNESS[4] = float[](0.4, 0.6, 0.8, 1.0);
+// the same as ChunkVertex::MAX_LIGHT
+const uint MAX_LIGHT = 15;
+// every light level is this much darker than the one above it
+const float LIGHT_FALLOFF = 0.8;
+// so caves arent completely black
+const float MIN_BRIGHTNESS = 0.03;
+
 void main() {
+    // z is stored one higher so it never goes below 0
+    vec3 v_pos = vec3(bitfieldExtract(v_data.x, 0, 5), bitfieldExtract(v_data.x, 5, 5), float(bitfieldExtract(v_data.x, 10, 5)) - 1.0);
+    uvec2 v_uv = uvec2(bitfieldExtract(v_data.x, 15, 5), bitfieldExtract(v_data.x, 20, 5));
+    uint v_tile = bitfieldExtract(v_data.y, 0, 8);
+    uint v_ao = bitfieldExtract(v_data.y, 8, 2);
+    uint v_sky_light = bitfieldExtract(v_data.y, 10, 4);
+    uvec3 v_block_light = uvec3(bitfieldExtract(v_data.y, 14, 4), bitfieldExtract(v_data.y, 18, 4), bitfieldExtract(v_data.y, 22, 4));
+
     gl_Position = proj * view * model * vec4(v_pos.x, v_pos.y * -1, v_pos.z, 1.0);
-    v_uv_out = v_uv;
+    v_uv_out = vec2(v_uv);
+    v_tile_out = vec2(v_tile % ATLAS_TILES, v_tile / ATLAS_TILES) / float(ATLAS_TILES);
+    v_ao_out = AO_BRIGHTNESS[v_ao];
+    // sky and block light dont add up, the brighter one wins in each colour
+    vec3 light = vec3(max(uvec3(v_sky_light), v_block_light));
+    v_light_out = max(pow(vec3(LIGHT_FALLOFF), float(MAX_LIGHT) - light), vec3(MIN_BRIGHTNESS));
 }
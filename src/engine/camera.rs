@@ -24,6 +24,7 @@ impl Camera {
 
     pub const SPEED: f32 = 5.0;
     pub const SENSITIVITY: f32 = 20.0;
+    pub const ROTATE_KEY: glfw::Key = glfw::Key::Tab;
 
     pub fn new(position: Vec3, direction: Vec3) -> Camera {
         let projection = glm::perspective_fov_rh_zo(45.0f32.to_radians(), WINDOW_WIDTH as f32, WINDOW_HEIGHT as f32, 0.1, 10000.0);
@@ -57,6 +58,10 @@ impl Camera {
         self.position
     }
 
+    pub fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
     pub fn descriptor_buffer_info(&self) -> vk::DescriptorBufferInfo {
         vk::DescriptorBufferInfo::builder()
             .buffer(self.uniform.buffer())
@@ -96,8 +101,9 @@ impl Camera {
             self.position += (local_up * Camera::SPEED) * delta_time;
         }
 
-        // if right mouse is clicked then go into rotating mode, if clicked again then stop
-        if window.get_mouse_button(glfw::MouseButtonRight) == glfw::Action::Press && self.accept_input {
+        // if the rotate key is pressed then go into rotating mode, if pressed again then stop
+        // (the mouse buttons are for breaking and placing blocks)
+        if window.get_key(Camera::ROTATE_KEY) == glfw::Action::Press && self.accept_input {
             if self.rotating {
                 self.rotating = false;
 
@@ -110,7 +116,7 @@ impl Camera {
             }
             
             self.accept_input = false;
-        } else if window.get_mouse_button(glfw::MouseButtonRight) == glfw::Action::Release {
+        } else if window.get_key(Camera::ROTATE_KEY) == glfw::Action::Release {
             self.accept_input = true;
         }
 
@@ -12,12 +12,12 @@ pub mod texture;
 /// if a type doesnt have a `.null()` function then i wrap it in an `Option`
 #[allow(non_upper_case_globals)]
 pub mod instance {
-    use std::{ffi::{CString, CStr}, ptr::null};
+    use std::{ffi::{CString, CStr}, mem::size_of, ptr::null};
     use ash::vk;
     use winapi::um::libloaderapi::GetModuleHandleW;
     use crate::WINDOW_TITLE;
 
-    use super::vertex::Vertex;
+    use super::vertex::ChunkVertex;
 
     pub const DEBUG: bool = true;
 
@@ -54,6 +54,8 @@ pub mod instance {
 
     static mut pipeline_layout: vk::PipelineLayout = vk::PipelineLayout::null();
     static mut graphics_pipeline: vk::Pipeline = vk::Pipeline::null();
+    /// same shaders as `graphics_pipeline` but blended and without writing depth
+    static mut translucent_pipeline: vk::Pipeline = vk::Pipeline::null();
 
     static mut graphics_command_pool: vk::CommandPool = vk::CommandPool::null();
     static mut descriptor_set_layout: vk::DescriptorSetLayout = vk::DescriptorSetLayout::null();
@@ -67,7 +69,11 @@ pub mod instance {
 
     type BufferOffset = u64;
     type Count = u64;
-    static mut draw_calls: Vec<(vk::Buffer, BufferOffset, vk::DescriptorSet, Count)> = Vec::new();
+    /// the count is of indices when theres an index buffer and of vertices when there isnt
+    type DrawCall = (vk::Buffer, BufferOffset, Option<vk::Buffer>, vk::DescriptorSet, Count);
+    static mut draw_calls: Vec<DrawCall> = Vec::new();
+    /// drawn with `translucent_pipeline` after all of `draw_calls`
+    static mut translucent_draw_calls: Vec<DrawCall> = Vec::new();
 
     pub fn init(glfw: &glfw::Glfw, window: &glfw::Window) {
         unsafe {
@@ -358,8 +364,28 @@ pub mod instance {
             None
         ).unwrap();
 
+        // ALPHA_CUTOFF in default.frag, cutout blocks get holes where the texture is mostly see-through
+        // while translucent ones keep all of it and get blended instead
+        let alpha_cutoff_entries = [
+            vk::SpecializationMapEntry {
+                constant_id: 0,
+                offset: 0,
+                size: size_of::<f32>()
+            }
+        ];
+        let opaque_alpha_cutoff = 0.5f32.to_ne_bytes();
+        let translucent_alpha_cutoff = 0.0f32.to_ne_bytes();
+        let opaque_specialization = vk::SpecializationInfo::builder()
+            .map_entries(&alpha_cutoff_entries)
+            .data(&opaque_alpha_cutoff)
+            .build();
+        let translucent_specialization = vk::SpecializationInfo::builder()
+            .map_entries(&alpha_cutoff_entries)
+            .data(&translucent_alpha_cutoff)
+            .build();
+
         let entry_point_name = CString::new("main").unwrap();
-        let shader_stages = [
+        let shader_stages = [&opaque_specialization, &translucent_specialization].map(|specialization| [
             vk::PipelineShaderStageCreateInfo::builder()
                 .stage(vk::ShaderStageFlags::VERTEX)
                 .module(vertex_shader_module)
@@ -369,14 +395,16 @@ pub mod instance {
                 .stage(vk::ShaderStageFlags::FRAGMENT)
                 .module(fragment_shader_module)
                 .name(&entry_point_name)
+                .specialization_info(specialization)
                 .build()
-        ];
+        ]);
         if DEBUG {
             println!("Created Shader Stages");
         }
 
-        let vertex_binding_description = Vertex::get_binding_description();
-        let vertex_attribute_descriptions = Vertex::get_attribute_descriptions();
+        // both pipelines draw chunks
+        let vertex_binding_description = ChunkVertex::get_binding_description();
+        let vertex_attribute_descriptions = ChunkVertex::get_attribute_descriptions();
 
         let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
             .vertex_binding_descriptions(&vertex_binding_description)
@@ -425,6 +453,11 @@ pub mod instance {
             .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
             .depth_bias_enable(false)
             .build();
+        // the surface of water should still be there when looking at it from below
+        let translucent_rasterizer_info = vk::PipelineRasterizationStateCreateInfo {
+            cull_mode: vk::CullModeFlags::NONE,
+            ..rasterizer_info
+        };
         if DEBUG {
             println!("Created Rasterizer State Info");
         }
@@ -448,6 +481,24 @@ pub mod instance {
             .attachments(&color_blend_attachments)
             .logic_op_enable(false)
             .build();
+
+        let translucent_color_blend_attachments = [
+            vk::PipelineColorBlendAttachmentState::builder()
+                .color_write_mask(vk::ColorComponentFlags::R | vk::ColorComponentFlags::G | vk::ColorComponentFlags::B | vk::ColorComponentFlags::A)
+                .blend_enable(true)
+                .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+                .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .color_blend_op(vk::BlendOp::ADD)
+                .src_alpha_blend_factor(vk::BlendFactor::ONE)
+                .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+                .alpha_blend_op(vk::BlendOp::ADD)
+                .build()
+        ];
+
+        let translucent_color_blend_info = vk::PipelineColorBlendStateCreateInfo::builder()
+            .attachments(&translucent_color_blend_attachments)
+            .logic_op_enable(false)
+            .build();
         if DEBUG {
             println!("Created Color Blend State Info");
         }
@@ -636,13 +687,18 @@ pub mod instance {
             .depth_bounds_test_enable(false)
             .stencil_test_enable(false)
             .build();
+        // translucent blocks are sorted back to front instead, they still get hidden behind solid blocks
+        let translucent_depth_stencil_info = vk::PipelineDepthStencilStateCreateInfo {
+            depth_write_enable: vk::FALSE,
+            ..depth_stencil_info
+        };
         if DEBUG {
             println!("Created Depth Stencil Info");
         }
 
-        graphics_pipeline = device.as_ref().unwrap().create_graphics_pipelines(vk::PipelineCache::null(), &[
+        let pipelines = device.as_ref().unwrap().create_graphics_pipelines(vk::PipelineCache::null(), &[
             vk::GraphicsPipelineCreateInfo::builder()
-                .stages(&shader_stages)
+                .stages(&shader_stages[0])
                 .vertex_input_state(&vertex_input_info)
                 .input_assembly_state(&input_assembly_info)
                 .viewport_state(&viewport_state_info)
@@ -653,11 +709,26 @@ pub mod instance {
                 .layout(pipeline_layout)
                 .render_pass(render_pass)
                 .subpass(0)
+                .build(),
+            vk::GraphicsPipelineCreateInfo::builder()
+                .stages(&shader_stages[1])
+                .vertex_input_state(&vertex_input_info)
+                .input_assembly_state(&input_assembly_info)
+                .viewport_state(&viewport_state_info)
+                .rasterization_state(&translucent_rasterizer_info)
+                .multisample_state(&multisample_info)
+                .depth_stencil_state(&translucent_depth_stencil_info)
+                .color_blend_state(&translucent_color_blend_info)
+                .layout(pipeline_layout)
+                .render_pass(render_pass)
+                .subpass(0)
                 .build()
-        ], None).unwrap()[0];
+        ], None).unwrap();
+        graphics_pipeline = pipelines[0];
+        translucent_pipeline = pipelines[1];
 
         if DEBUG {
-            println!("Created Graphics Pipeline");
+            println!("Created Graphics Pipelines");
         }
     }
 
@@ -744,13 +815,72 @@ pub mod instance {
 
     /// pushes a draw "command" to a vector
     /// 
+    /// with an `index_buffer` (of u32s, starting at 0) `count` is the number of indices to draw, otherwise its the number of vertices
+    /// 
     /// note: writing of descriptor sets is not handled by engine
-    pub fn draw(vertex_buffer: vk::Buffer, offset: u64, descriptor_set: vk::DescriptorSet, vertex_count: u64) {
+    pub fn draw(vertex_buffer: vk::Buffer, offset: u64, index_buffer: Option<vk::Buffer>, descriptor_set: vk::DescriptorSet, count: u64) {
         unsafe {
-            draw_calls.push((vertex_buffer, offset, descriptor_set, vertex_count));
+            draw_calls.push((vertex_buffer, offset, index_buffer, descriptor_set, count));
         }
     }
 
+    /// like `draw` but blended over everything from `draw`, in the order the calls come in so they should go back to front
+    pub fn draw_translucent(vertex_buffer: vk::Buffer, offset: u64, index_buffer: Option<vk::Buffer>, descriptor_set: vk::DescriptorSet, count: u64) {
+        unsafe {
+            translucent_draw_calls.push((vertex_buffer, offset, index_buffer, descriptor_set, count));
+        }
+    }
+
+
+    /// records `calls` into the draw command buffer with whatever pipeline is bound
+    unsafe fn record_draw_calls(calls: &[DrawCall]) {
+        for call in calls {
+            device.as_ref().unwrap().cmd_bind_descriptor_sets(
+                draw_command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                0,
+                &[call.3],
+                &[]
+            );
+
+            device.as_ref().unwrap().cmd_bind_vertex_buffers(
+                draw_command_buffer,
+                0,
+                &[call.0],
+                &[call.1]
+            );
+
+            match call.2 {
+                Some(index_buffer) => {
+                    device.as_ref().unwrap().cmd_bind_index_buffer(
+                        draw_command_buffer,
+                        index_buffer,
+                        0,
+                        vk::IndexType::UINT32
+                    );
+
+                    device.as_ref().unwrap().cmd_draw_indexed(
+                        draw_command_buffer,
+                        call.4 as u32,
+                        1,
+                        0,
+                        0,
+                        0
+                    );
+                }
+                None => {
+                    device.as_ref().unwrap().cmd_draw(
+                        draw_command_buffer,
+                        call.4 as u32,
+                        1,
+                        0,
+                        0
+                    );
+                }
+            }
+        }
+    }
 
     /// note: again i repeat, writing of descriptor sets is not handled by engine
     pub fn render_surface() {
@@ -789,34 +919,13 @@ pub mod instance {
             );
 
             device.as_ref().unwrap().cmd_bind_pipeline(draw_command_buffer, vk::PipelineBindPoint::GRAPHICS, graphics_pipeline);
-
-            for call in draw_calls.iter() {
-                device.as_ref().unwrap().cmd_bind_descriptor_sets(
-                    draw_command_buffer,
-                    vk::PipelineBindPoint::GRAPHICS,
-                    pipeline_layout,
-                    0,
-                    &[call.2],
-                    &[]
-                );
-
-                device.as_ref().unwrap().cmd_bind_vertex_buffers(
-                    draw_command_buffer,
-                    0,
-                    &[call.0],
-                    &[call.1]
-                );
-
-                device.as_ref().unwrap().cmd_draw(
-                    draw_command_buffer,
-                    call.3 as u32,
-                    1,
-                    0,
-                    0
-                );
-            }
+            record_draw_calls(&draw_calls);
             draw_calls.clear();
 
+            device.as_ref().unwrap().cmd_bind_pipeline(draw_command_buffer, vk::PipelineBindPoint::GRAPHICS, translucent_pipeline);
+            record_draw_calls(&translucent_draw_calls);
+            translucent_draw_calls.clear();
+
             device.as_ref().unwrap().cmd_end_render_pass(draw_command_buffer);
             device.as_ref().unwrap().end_command_buffer(draw_command_buffer).unwrap();
 
@@ -1,7 +1,8 @@
 use ash::vk;
 
+/// for geometry that isnt part of a chunk, chunk meshes use the smaller `ChunkVertex`
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vertex {
     position: [f32; 3],
     uv: [f32; 2]
@@ -50,3 +51,130 @@ impl Vertex {
         ]
     }
 }
+
+/// a vertex of a chunk mesh packed into 8 bytes, `default.vert` unpacks it again
+///
+/// first u32: x, y and z + 1 (5 bits each), u and v (5 bits each), face (3 bits)
+/// second u32: tile (8 bits), ao (2 bits), sky light (4 bits), red, green and blue block light (4 bits each)
+///
+/// positions are relative to the chunk and go from 0 to `Chunk::SIZE`, except z which goes one lower
+/// since chunk meshes go from z - 1 to z, thats why its stored one higher
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkVertex {
+    data: [u32; 2]
+}
+
+impl ChunkVertex {
+    pub const MAX_AO: u8 = 3;
+    pub const MAX_LIGHT: u8 = 15;
+
+    /// `position` and `uv` get rounded, `face` is an index into `mesher::Face::ALL`,
+    /// `ao` goes from 0 (fully in a corner) to `MAX_AO` and the light levels from 0 to `MAX_LIGHT`
+    pub fn new(position: glm::Vec3, uv: glm::Vec2, face: u8, tile: u16, ao: u8, sky_light: u8, block_light: [u8; 3]) -> ChunkVertex {
+        let [x, y, z] = [position.x.round() as i32, position.y.round() as i32, position.z.round() as i32 + 1];
+        let [u, v] = [uv.x.round() as i32, uv.y.round() as i32];
+
+        assert!([x, y, z, u, v].iter().all(|value| (0..32).contains(value)), "{position} {uv} doesnt fit into a chunk vertex");
+        assert!(face < 6 && tile < 256 && ao <= ChunkVertex::MAX_AO, "face {face}, tile {tile} or ao {ao} doesnt fit into a chunk vertex");
+        assert!(sky_light <= ChunkVertex::MAX_LIGHT && block_light.iter().all(|l| *l <= ChunkVertex::MAX_LIGHT), "light {sky_light}, {block_light:?} doesnt fit into a chunk vertex");
+
+        ChunkVertex {
+            data: [
+                x as u32 | (y as u32) << 5 | (z as u32) << 10 | (u as u32) << 15 | (v as u32) << 20 | (face as u32) << 25,
+                tile as u32 | (ao as u32) << 8 | (sky_light as u32) << 10 | (block_light[0] as u32) << 14 | (block_light[1] as u32) << 18 | (block_light[2] as u32) << 22
+            ]
+        }
+    }
+
+    fn bits(&self, word: usize, offset: u32, count: u32) -> u32 {
+        (self.data[word] >> offset) & ((1 << count) - 1)
+    }
+
+    pub fn position(&self) -> glm::Vec3 {
+        glm::vec3(self.bits(0, 0, 5) as f32, self.bits(0, 5, 5) as f32, self.bits(0, 10, 5) as f32 - 1.0)
+    }
+
+    pub fn uv(&self) -> glm::Vec2 {
+        glm::vec2(self.bits(0, 15, 5) as f32, self.bits(0, 20, 5) as f32)
+    }
+
+    pub fn face(&self) -> u8 {
+        self.bits(0, 25, 3) as u8
+    }
+
+    pub fn tile(&self) -> u16 {
+        self.bits(1, 0, 8) as u16
+    }
+
+    pub fn ao(&self) -> u8 {
+        self.bits(1, 8, 2) as u8
+    }
+
+    pub fn sky_light(&self) -> u8 {
+        self.bits(1, 10, 4) as u8
+    }
+
+    /// red, green and blue
+    pub fn block_light(&self) -> [u8; 3] {
+        [self.bits(1, 14, 4) as u8, self.bits(1, 18, 4) as u8, self.bits(1, 22, 4) as u8]
+    }
+
+    pub fn get_binding_description() -> [vk::VertexInputBindingDescription; 1] {
+        [
+            vk::VertexInputBindingDescription::builder()
+                .binding(0)
+                .stride(std::mem::size_of::<ChunkVertex>() as u32)
+                .input_rate(vk::VertexInputRate::VERTEX)
+                .build()
+        ]
+    }
+
+    pub fn get_attribute_descriptions() -> [vk::VertexInputAttributeDescription; 1] {
+        [
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(0)
+                .format(vk::Format::R32G32_UINT)
+                .offset(0)
+                .build()
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkVertex, Vertex};
+
+    #[test]
+    fn vertices_keep_floats() {
+        let vertex = Vertex::new(glm::vec3(0.25, -3.5, 100.0), glm::vec2(0.1, 0.9));
+        assert_eq!((vertex.position(), vertex.uv()), (glm::vec3(0.25, -3.5, 100.0), glm::vec2(0.1, 0.9)));
+
+        let uv = Vertex::get_attribute_descriptions()[1];
+        assert_eq!(uv.offset as usize, std::mem::size_of::<[f32; 3]>());
+        assert_eq!(std::mem::size_of::<Vertex>(), 20);
+    }
+
+    #[test]
+    fn chunk_vertices_unpack_to_what_was_packed() {
+        for (position, uv, face, tile, ao, sky_light, block_light) in [
+            (glm::vec3(0.0, 0.0, -1.0), glm::vec2(0.0, 0.0), 0, 0, 0, 0, [0; 3]),
+            (glm::vec3(20.0, 20.0, 19.0), glm::vec2(20.0, 20.0), 5, 99, 3, 15, [15; 3]),
+            (glm::vec3(5.0, 17.0, 3.0), glm::vec2(1.0, 12.0), 2, 255, 1, 7, [12, 0, 5])
+        ] {
+            let vertex = ChunkVertex::new(position, uv, face, tile, ao, sky_light, block_light);
+
+            assert_eq!((vertex.position(), vertex.uv(), vertex.face(), vertex.tile(), vertex.ao()), (position, uv, face, tile, ao));
+            assert_eq!((vertex.sky_light(), vertex.block_light()), (sky_light, block_light));
+        }
+
+        assert_eq!(std::mem::size_of::<ChunkVertex>(), 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunk_vertices_outside_of_the_chunk_dont_pack() {
+        ChunkVertex::new(glm::vec3(0.0, 0.0, -2.0), glm::vec2(0.0, 0.0), 0, 0, 0, 0, [0; 3]);
+    }
+}
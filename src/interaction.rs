@@ -0,0 +1,113 @@
+use glfw::Window;
+use crate::{engine::camera::Camera, world::{World, block::BlockId, chunk::GlobalPos, raycast}};
+
+/// everything about breaking and placing blocks that can be changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InteractionSettings {
+    /// how far away blocks can be broken and placed
+    pub reach: f32,
+    /// seconds between breaking or placing blocks while a button is held down
+    pub cooldown: f32,
+    pub break_button: glfw::MouseButton,
+    pub place_button: glfw::MouseButton,
+    /// size of the player, placing a block inside of it isnt allowed
+    pub player_width: f32,
+    pub player_height: f32,
+    /// how far below the top of the player the camera is
+    pub eye_offset: f32
+}
+
+impl Default for InteractionSettings {
+    fn default() -> InteractionSettings {
+        InteractionSettings {
+            reach: 6.0,
+            cooldown: 0.2,
+            break_button: glfw::MouseButtonLeft,
+            place_button: glfw::MouseButtonRight,
+            player_width: 0.6,
+            player_height: 1.8,
+            eye_offset: 0.2
+        }
+    }
+}
+
+/// breaks and places the block the camera is looking at
+///
+/// the number keys pick which block gets placed, 1 is the first block after air in the registry and 0 the tenth.
+/// holding shift picks from the next ten blocks
+pub struct BlockInteraction {
+    settings: InteractionSettings,
+    selected_block: BlockId,
+    /// seconds until the next block can be broken or placed
+    cooldown: f32
+}
+
+impl BlockInteraction {
+    const NUMBER_KEYS: [glfw::Key; 10] = [
+        glfw::Key::Num1, glfw::Key::Num2, glfw::Key::Num3,
+        glfw::Key::Num4, glfw::Key::Num5, glfw::Key::Num6,
+        glfw::Key::Num7, glfw::Key::Num8, glfw::Key::Num9,
+        glfw::Key::Num0
+    ];
+
+    pub fn new(settings: InteractionSettings) -> BlockInteraction {
+        BlockInteraction {
+            settings,
+            selected_block: BlockId(1),
+            cooldown: 0.0
+        }
+    }
+
+    pub fn selected_block(&self) -> BlockId {
+        self.selected_block
+    }
+
+    pub fn inputs(&mut self, window: &Window, camera: &Camera, world: &mut World, delta_time: f32) {
+        let page = if window.get_key(glfw::Key::LeftShift) == glfw::Action::Press { BlockInteraction::NUMBER_KEYS.len() } else { 0 };
+        for (i, key) in BlockInteraction::NUMBER_KEYS.iter().enumerate() {
+            let i = page + i;
+            if window.get_key(*key) == glfw::Action::Press && i + 1 < world.registry().len() {
+                self.selected_block = BlockId(i as u16 + 1);
+            }
+        }
+
+        self.cooldown = (self.cooldown - delta_time).max(0.0);
+        if self.cooldown > 0.0 {
+            return;
+        }
+
+        let breaking = window.get_mouse_button(self.settings.break_button) == glfw::Action::Press;
+        let placing = window.get_mouse_button(self.settings.place_button) == glfw::Action::Press;
+        if !breaking && !placing {
+            return;
+        }
+
+        let hit = match world.raycast(camera.position(), camera.direction(), self.settings.reach) {
+            Some(hit) => hit,
+            None => return
+        };
+
+        if breaking {
+            world.set_block(hit.position, BlockId::AIR);
+        } else {
+            // the ray started inside of a block, theres no face to place against
+            if hit.normal == glm::vec3(0, 0, 0) || self.overlaps_player(hit.adjacent, camera.position()) {
+                return;
+            }
+
+            world.set_block(hit.adjacent, self.selected_block);
+        }
+
+        self.cooldown = self.settings.cooldown;
+    }
+
+    fn overlaps_player(&self, block: GlobalPos, camera_position: glm::Vec3) -> bool {
+        let eye = raycast::to_block_space(camera_position);
+        let half_width = self.settings.player_width / 2.0;
+
+        let min = glm::vec3(eye.x - half_width, eye.y + self.settings.eye_offset - self.settings.player_height, eye.z - half_width);
+        let max = glm::vec3(eye.x + half_width, eye.y + self.settings.eye_offset, eye.z + half_width);
+
+        (0..3).all(|axis| min[axis] < block[axis] as f32 + 1.0 && max[axis] > block[axis] as f32)
+    }
+}
@@ -0,0 +1,436 @@
+use std::fmt::{self, Display, Write};
+
+/// just enough json to read and write config/save files without pulling in serde
+///
+/// objects keep the order they were written in
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {} column {}", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl JsonValue {
+    pub fn parse(text: &str) -> Result<JsonValue, ParseError> {
+        let mut parser = Parser {
+            text,
+            chars: text.char_indices().peekable()
+        };
+
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        if parser.chars.peek().is_some() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+
+        Ok(value)
+    }
+
+    /// returns None if this isnt an object or the key doesnt exist
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    /// replaces the value of `key` or adds it at the end, does nothing if this isnt an object
+    pub fn set(&mut self, key: &str, value: JsonValue) {
+        if let JsonValue::Object(fields) = self {
+            match fields.iter_mut().find(|(k, _)| k == key) {
+                Some((_, v)) => *v = value,
+                None => fields.push((key.to_string(), value))
+            }
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None
+        }
+    }
+
+    /// returns None if the number isnt a whole number
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_f64().filter(|n| n.fract() == 0.0).map(|n| n as i64)
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(a) => Some(a),
+            _ => None
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&[(String, JsonValue)]> {
+        match self {
+            JsonValue::Object(o) => Some(o),
+            _ => None
+        }
+    }
+
+    /// human readable name of the type for error messages
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Null => "null",
+            JsonValue::Bool(_) => "bool",
+            JsonValue::Number(_) => "number",
+            JsonValue::String(_) => "string",
+            JsonValue::Array(_) => "array",
+            JsonValue::Object(_) => "object"
+        }
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize) -> fmt::Result {
+        match self {
+            JsonValue::Null => out.write_str("null"),
+            JsonValue::Bool(b) => write!(out, "{b}"),
+            // `{}` on f64 prints whole numbers without a trailing `.0` and round trips everything else
+            JsonValue::Number(n) if n.is_finite() => write!(out, "{n}"),
+            JsonValue::Number(_) => out.write_str("null"),
+            JsonValue::String(s) => write_string(out, s),
+            JsonValue::Array(a) if a.is_empty() => out.write_str("[]"),
+            JsonValue::Array(a) => {
+                out.write_str("[\n")?;
+                for (i, value) in a.iter().enumerate() {
+                    write!(out, "{:1$}", "", (indent + 1) * 4)?;
+                    value.write_pretty(out, indent + 1)?;
+                    out.write_str(if i + 1 < a.len() { ",\n" } else { "\n" })?;
+                }
+                write!(out, "{:1$}]", "", indent * 4)
+            }
+            JsonValue::Object(o) if o.is_empty() => out.write_str("{}"),
+            JsonValue::Object(o) => {
+                out.write_str("{\n")?;
+                for (i, (key, value)) in o.iter().enumerate() {
+                    write!(out, "{:1$}", "", (indent + 1) * 4)?;
+                    write_string(out, key)?;
+                    out.write_str(": ")?;
+                    value.write_pretty(out, indent + 1)?;
+                    out.write_str(if i + 1 < o.len() { ",\n" } else { "\n" })?;
+                }
+                write!(out, "{:1$}}}", "", indent * 4)
+            }
+        }
+    }
+}
+
+/// pretty prints with 4 space indentation
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0)?;
+        f.write_str(&out)
+    }
+}
+
+fn write_string(out: &mut String, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?
+        }
+    }
+    out.write_char('"')
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>
+}
+
+impl<'a> Parser<'a> {
+    fn error(&mut self, message: impl ToString) -> ParseError {
+        let offset = self.chars.peek().map(|(i, _)| *i).unwrap_or(self.text.len());
+        let before = &self.text[..offset];
+
+        ParseError {
+            line: before.matches('\n').count() + 1,
+            column: before.chars().rev().take_while(|c| *c != '\n').count() + 1,
+            message: message.to_string()
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParseError> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some((_, c)) if *c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            Some((_, c)) => {
+                let c = *c;
+                Err(self.error(format!("expected '{expected}' but found '{c}'")))
+            }
+            None => Err(self.error(format!("expected '{expected}' but found end of file")))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<JsonValue, ParseError> {
+        self.skip_whitespace();
+
+        match self.chars.peek().map(|(_, c)| *c) {
+            Some('{') => self.parse_object(),
+            Some('[') => self.parse_array(),
+            Some('"') => Ok(JsonValue::String(self.parse_string()?)),
+            Some('t') => self.parse_literal("true", JsonValue::Bool(true)),
+            Some('f') => self.parse_literal("false", JsonValue::Bool(false)),
+            Some('n') => self.parse_literal("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+            Some(c) => Err(self.error(format!("unexpected character '{c}'"))),
+            None => Err(self.error("unexpected end of file"))
+        }
+    }
+
+    fn parse_literal(&mut self, literal: &str, value: JsonValue) -> Result<JsonValue, ParseError> {
+        for expected in literal.chars() {
+            if self.chars.next_if(|(_, c)| *c == expected).is_none() {
+                return Err(self.error(format!("invalid literal, expected \"{literal}\"")));
+            }
+        }
+
+        Ok(value)
+    }
+
+    fn parse_number(&mut self) -> Result<JsonValue, ParseError> {
+        let start = self.chars.peek().map(|(i, _)| *i).unwrap();
+
+        // the grammar from json.org, rusts f64 parser alone would also take things like `1.` or `+1`
+        self.chars.next_if(|(_, c)| *c == '-');
+        if self.chars.next_if(|(_, c)| *c == '0').is_none() && self.skip_digits() == 0 {
+            return Err(self.error("expected a digit"));
+        }
+        if self.chars.next_if(|(_, c)| *c == '.').is_some() && self.skip_digits() == 0 {
+            return Err(self.error("expected a digit after the decimal point"));
+        }
+        if self.chars.next_if(|(_, c)| matches!(c, 'e' | 'E')).is_some() {
+            self.chars.next_if(|(_, c)| matches!(c, '-' | '+'));
+            if self.skip_digits() == 0 {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+        }
+
+        let end = self.chars.peek().map(|(i, _)| *i).unwrap_or(self.text.len());
+        self.text[start..end].parse::<f64>()
+            .map(JsonValue::Number)
+            .map_err(|_| self.error(format!("invalid number \"{}\"", &self.text[start..end])))
+    }
+
+    /// returns how many digits were skipped
+    fn skip_digits(&mut self) -> usize {
+        let mut count = 0;
+        while self.chars.next_if(|(_, c)| c.is_ascii_digit()).is_some() {
+            count += 1;
+        }
+
+        count
+    }
+
+    /// the 4 hex digits after a `\u`
+    fn parse_hex_escape(&mut self) -> Result<u32, ParseError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            let digit = self.chars.next().and_then(|(_, c)| c.to_digit(16));
+            match digit {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid unicode escape"))
+            }
+        }
+
+        Ok(code)
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        self.expect('"')?;
+
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, '/')) => s.push('/'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((_, 'u')) => {
+                        let mut code = self.parse_hex_escape()?;
+
+                        // anything outside of the basic plane is written as a utf-16 surrogate pair
+                        if (0xd800..0xdc00).contains(&code) {
+                            if self.chars.next_if(|(_, c)| *c == '\\').is_none() || self.chars.next_if(|(_, c)| *c == 'u').is_none() {
+                                return Err(self.error("unpaired surrogate in unicode escape"));
+                            }
+                            let low = self.parse_hex_escape()?;
+                            if !(0xdc00..0xe000).contains(&low) {
+                                return Err(self.error("unpaired surrogate in unicode escape"));
+                            }
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        }
+
+                        match char::from_u32(code) {
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("unpaired surrogate in unicode escape"))
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape sequence"))
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err(self.error("unterminated string"))
+            }
+        }
+    }
+
+    fn parse_array(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('[')?;
+
+        let mut values = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == ']').is_some() {
+            return Ok(JsonValue::Array(values));
+        }
+
+        loop {
+            values.push(self.parse_value()?);
+
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, ']')) => return Ok(JsonValue::Array(values)),
+                _ => return Err(self.error("expected ',' or ']' in array"))
+            }
+        }
+    }
+
+    fn parse_object(&mut self) -> Result<JsonValue, ParseError> {
+        self.expect('{')?;
+
+        let mut entries: Vec<(String, JsonValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.chars.next_if(|(_, c)| *c == '}').is_some() {
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            if entries.iter().any(|(k, _)| *k == key) {
+                return Err(self.error(format!("duplicate key \"{key}\"")));
+            }
+
+            self.expect(':')?;
+            let value = self.parse_value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.chars.next() {
+                Some((_, ',')) => continue,
+                Some((_, '}')) => return Ok(JsonValue::Object(entries)),
+                _ => return Err(self.error("expected ',' or '}' in object"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonValue;
+
+    fn parse_error(text: &str) -> (usize, usize, String) {
+        let error = JsonValue::parse(text).unwrap_err();
+        (error.line, error.column, error.message)
+    }
+
+    #[test]
+    fn parses_escapes() {
+        let value = JsonValue::parse(r#""a\"b\\c\/d\n\r\t\b\fé😀""#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"b\\c/d\n\r\t\u{8}\u{c}é😀"));
+
+        assert!(JsonValue::parse(r#""\ud83d""#).is_err());
+        assert!(JsonValue::parse(r#""\ud83dA""#).is_err());
+        assert!(JsonValue::parse(r#""\ude00""#).is_err());
+        assert!(JsonValue::parse(r#""\u12""#).is_err());
+        assert!(JsonValue::parse(r#""\x""#).is_err());
+        assert!(JsonValue::parse(r#""abc"#).is_err());
+    }
+
+    #[test]
+    fn parses_numbers() {
+        for (text, number) in [("0", 0.0), ("-0", 0.0), ("12", 12.0), ("-3.25", -3.25), ("1e3", 1000.0), ("2.5E-1", 0.25), ("1e+2", 100.0)] {
+            assert_eq!(JsonValue::parse(text).unwrap(), JsonValue::Number(number), "{text}");
+        }
+
+        for text in ["1.", ".5", "-", "+1", "01", "1e", "1e+", "1.e3", "--1", "1-2"] {
+            assert!(JsonValue::parse(text).is_err(), "{text} shouldnt parse");
+        }
+    }
+
+    #[test]
+    fn rejects_duplicate_keys() {
+        let (line, column, message) = parse_error("{\n    \"a\": 1,\n    \"a\": 2\n}");
+        assert_eq!(message, "duplicate key \"a\"");
+        assert_eq!((line, column), (3, 8));
+    }
+
+    #[test]
+    fn errors_point_at_the_problem() {
+        assert_eq!(parse_error("[1, 2,\n  x]"), (2, 3, "unexpected character 'x'".to_string()));
+        assert_eq!(parse_error("{\"a\" 1}"), (1, 6, "expected ':' but found '1'".to_string()));
+        assert_eq!(parse_error("[1, 2"), (1, 6, "expected ',' or ']' in array".to_string()));
+        assert_eq!(parse_error("true false"), (1, 6, "unexpected trailing characters".to_string()));
+        assert_eq!(parse_error(""), (1, 1, "unexpected end of file".to_string()));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        let text = r#"{"name": "a \"quoted\"\nline\u0001", "numbers": [0, -1.5, 1e20, 0.1], "empty": {}, "nested": [[], [true, false, null]]}"#;
+        let value = JsonValue::parse(text).unwrap();
+        let written = value.to_string();
+
+        assert_eq!(JsonValue::parse(&written).unwrap(), value);
+        assert_eq!(JsonValue::parse(&written).unwrap().to_string(), written);
+    }
+}
@@ -1,16 +1,23 @@
 pub mod engine;
+pub mod interaction;
+pub mod json;
 pub mod timer;
 pub mod world;
 
-use std::{io::Cursor, mem::size_of, time::Instant};
+use std::{io::Cursor, mem::size_of, panic::AssertUnwindSafe, path::Path, time::Instant};
 use ash::vk;
 use engine::{camera::{Camera, CameraUniform}, buffer::Buffer, vertex::Vertex, texture::Texture};
 use timer::Timer;
-use world::{World, chunk::{Chunk, build_mesh}, block::{Block, BlockType}};
+use interaction::{BlockInteraction, InteractionSettings};
+use world::{World, chunk::Chunk, block::{Block, BlockType}, generation::{TerrainGenerator, biome::BiomeGenerator}, config::WorldConfig, region::RegionStorage, level::LevelData, id_map::BlockIdMap, raycast};
 
 pub const WINDOW_WIDTH: u32 = 1920;
 pub const WINDOW_HEIGHT: u32 = 1080;
 pub const WINDOW_TITLE: &str = "RustCraft";
+/// switches between greedy and naive meshing to compare them
+pub const MESHING_MODE_KEY: glfw::Key = glfw::Key::M;
+/// turns ambient occlusion on and off
+pub const AMBIENT_OCCLUSION_KEY: glfw::Key = glfw::Key::O;
 
 fn main() {
     let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
@@ -22,11 +29,38 @@ fn main() {
 
     engine::instance::init(&glfw, &window);
 
-    let mut camera = Camera::new(glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 0.0, -1.0));
-
     let texture = Texture::new(image::load(Cursor::new(std::fs::read("textures/atlas.png").unwrap()), image::ImageFormat::Png).unwrap().flipv());
 
-    let mut world = World::new(8);
+    let registry = world::definitions::load_block_definitions("blocks.json").unwrap_or_else(|e| panic!("Failed to load block definitions: {e}"));
+
+    let world_directory = Path::new("saves/world");
+    let level_path = world_directory.join("level.json");
+
+    let mut level = if level_path.exists() {
+        LevelData::load(&level_path).unwrap_or_else(|e| panic!("Failed to load level: {e}"))
+    } else {
+        // new worlds get their settings from the world config
+        let config = WorldConfig::load("world_config.json").unwrap_or_else(|e| panic!("Failed to load world config: {e}"));
+        let surface_height = BiomeGenerator::new(&config, &registry).surface_height(0, 0, config.seed).unwrap_or(0);
+
+        LevelData::new(config, raycast::to_camera_space(glm::vec3(0.5, surface_height as f32 + 1.6, 0.5)))
+    };
+
+    let mut camera = Camera::new(level.player_position, level.player_direction);
+
+    let generator = BiomeGenerator::new(&level.config, &registry);
+
+    // saved right away so the block ids are on disk before any chunk that uses them
+    let block_ids = BlockIdMap::new(level.block_ids.clone(), &registry);
+    level.block_ids = block_ids.names().to_vec();
+    std::fs::create_dir_all(world_directory).unwrap_or_else(|e| panic!("Failed to create world directory: {e}"));
+    level.save(&level_path).unwrap_or_else(|e| panic!("Failed to save level: {e}"));
+
+    let storage = RegionStorage::open(world_directory.join("region"), block_ids).unwrap_or_else(|e| panic!("Failed to open world save: {e}"));
+
+    let mut world = World::new(8, registry, generator, level.config.seed, storage, camera.position());
+
+    let mut block_interaction = BlockInteraction::new(InteractionSettings::default());
     // let mut chunk = Chunk::new(glm::vec3(0, -8, 0), |pos| {
     //     if pos.y < -2 {
     //         Block::new("Grass Block", "grass_block", BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.1, 0.0), glm::vec2(0.2, 0.0))
@@ -36,36 +70,97 @@ fn main() {
     // });
     // build_mesh(&chunk, [None, None, None, None, None, None]);
 
+    let save_settings = level.save_settings;
+
     let mut delta_timer = Timer::new();
     let mut fps_timer = Timer::new();
+    let mut autosave_timer = Timer::new();
     let mut fps_counter = 0;
-
-    while !window.should_close() {
-        glfw.poll_events();
-
-        delta_timer.tick();
-        let delta_time = delta_timer.elapsed();
-        delta_timer.reset();
-
-        fps_timer.tick();
-        fps_counter += 1;
-        if fps_timer.elapsed() > 1.0 {
-            println!("FPS: {}", fps_counter);
-            fps_counter = 0;
-            fps_timer.reset();
+    let mut meshing_key_down = false;
+    let mut ambient_occlusion_key_down = false;
+
+    // the world gets saved once the panic is out of the loop, saving from inside of the panic hook
+    // could run into whatever borrow of the world was active when it panicked.
+    // panics on the chunk workers get passed on to here by `World::update_world`
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        while !window.should_close() {
+            glfw.poll_events();
+
+            delta_timer.tick();
+            let delta_time = delta_timer.elapsed();
+            delta_timer.reset();
+
+            level.game_time += delta_time as f64;
+
+            fps_timer.tick();
+            fps_counter += 1;
+            if fps_timer.elapsed() > 1.0 {
+                println!("FPS: {}, vertices: {}", fps_counter, world.vertex_count());
+                fps_counter = 0;
+                fps_timer.reset();
+            }
+
+            camera.inputs(&mut window, delta_time);
+            block_interaction.inputs(&window, &camera, &mut world, delta_time);
+
+            // only switches once per press
+            let meshing_key_pressed = window.get_key(MESHING_MODE_KEY) == glfw::Action::Press;
+            if meshing_key_pressed && !meshing_key_down {
+                world.set_meshing_mode(world.meshing_mode().toggled());
+                println!("Meshing mode: {:?}", world.meshing_mode());
+            }
+            meshing_key_down = meshing_key_pressed;
+
+            let ambient_occlusion_key_pressed = window.get_key(AMBIENT_OCCLUSION_KEY) == glfw::Action::Press;
+            if ambient_occlusion_key_pressed && !ambient_occlusion_key_down {
+                world.set_ambient_occlusion(!world.ambient_occlusion());
+                println!("Ambient occlusion: {}", world.ambient_occlusion());
+            }
+            ambient_occlusion_key_down = ambient_occlusion_key_pressed;
+
+            world.update_world(camera.position());
+
+            autosave_timer.tick();
+            if save_settings.autosave_interval > 0.0 && autosave_timer.elapsed() > save_settings.autosave_interval {
+                save(&mut world, &mut level, &camera, &level_path);
+                autosave_timer.reset();
+            }
+
+            world.draw(camera.descriptor_buffer_info(), texture.descriptor_image_info());
+            // chunk.draw(camera.descriptor_buffer_info(), texture.descriptor_image_info());
+
+            engine::instance::render_surface();
         }
+    }));
 
-        camera.inputs(&mut window, delta_time);
-
-        world.update_world(camera.position());
-
-        world.draw(camera.descriptor_buffer_info(), texture.descriptor_image_info());
-        // chunk.draw(camera.descriptor_buffer_info(), texture.descriptor_image_info());
+    match result {
+        Ok(()) => {
+            if save_settings.save_on_exit {
+                save(&mut world, &mut level, &camera, &level_path);
+            }
+        }
+        Err(panic) => {
+            if save_settings.save_on_panic {
+                println!("Saving the world before exiting");
+                save(&mut world, &mut level, &camera, &level_path);
+            }
 
-        engine::instance::render_surface();
+            std::panic::resume_unwind(panic);
+        }
     }
 
     unsafe {
         engine::instance::get_device().device_wait_idle().unwrap();
     }
 }
+
+/// writes the edited chunks and the level file
+fn save(world: &mut World, level: &mut LevelData, camera: &Camera, level_path: &Path) {
+    world.save();
+
+    level.player_position = camera.position();
+    level.player_direction = camera.direction();
+    if let Err(e) = level.save(level_path) {
+        println!("Failed to save level: {e}");
+    }
+}
@@ -1,3 +1,5 @@
+use super::light::MAX_LIGHT;
+
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Block {
     pub name: String,
@@ -5,13 +7,24 @@ pub struct Block {
     pub block_type: BlockType,
     pub top_uv: glm::Vec2,
     pub side_uv: glm::Vec2,
-    pub bottom_uv: glm::Vec2
+    pub bottom_uv: glm::Vec2,
+    pub transparent: bool,
+    /// 0 for blocks that dont give off any light
+    pub light_emission: u8,
+    /// red, green and blue of the light it gives off, `light_emission` is how bright the brightest of them is
+    pub light_color: [u8; 3],
+    /// how much light it takes away on top of the 1 that every block takes, `light::MAX_LIGHT` lets nothing through
+    pub opacity: u8
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
 pub enum BlockType {
     Air,
-    Solid
+    Solid,
+    /// drawn with the solid blocks but with holes where the texture is see-through, like leaves
+    Cutout,
+    /// blended over whatever is behind it, like glass or water
+    Translucent
 }
 
 impl Block {
@@ -22,7 +35,30 @@ impl Block {
             block_type,
             top_uv,
             side_uv,
-            bottom_uv
+            bottom_uv,
+            transparent: block_type != BlockType::Solid,
+            light_emission: 0,
+            light_color: [255, 255, 255],
+            opacity: if block_type == BlockType::Solid { MAX_LIGHT } else { 0 }
+        }
+    }
+
+    /// the light level the block gives off in red, green and blue
+    pub fn emission(&self) -> [u8; 3] {
+        let brightest = self.light_color.iter().copied().max().unwrap();
+        if brightest == 0 {
+            return [0; 3];
         }
+
+        self.light_color.map(|c| ((self.light_emission as u32 * c as u32 + brightest as u32 / 2) / brightest as u32) as u8)
     }
 }
+
+/// index into a `BlockRegistry`, this is what chunks actually store instead of a whole `Block`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(pub u16);
+
+impl BlockId {
+    /// air is always registered first
+    pub const AIR: BlockId = BlockId(0);
+}
@@ -3,55 +3,169 @@
 /// and then give an offset to each chunk
 
 
-use std::{collections::HashMap, mem::size_of};
+use std::mem::{size_of, size_of_val};
 use ash::vk;
-use crate::engine::{buffer::Buffer, vertex::Vertex, self};
-use super::{block::{Block, BlockType}, World};
+use crate::engine::{buffer::Buffer, vertex::ChunkVertex, self};
+use super::{block::BlockId, palette::PalettedContainer, light::{ChunkLight, LightChannel}, mesher::{self, ChunkMesh}, World};
 
-type LocalPos = glm::I8Vec3;
+pub type LocalPos = glm::I8Vec3;
 pub type GlobalPos = glm::IVec3;
 pub type BufferOffset = u64;
 pub type Size = u64;
 pub type Count = u64;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct Chunk {
     position: glm::IVec3,
-    blocks: HashMap<LocalPos, Block>,
+    blocks: PalettedContainer<BlockId>,
+    /// isnt saved, `light::LightUpdate::light_chunk` works it out again whenever the chunk is loaded
+    light: ChunkLight,
     buffer_offset: BufferOffset,
-    mesh: Option<(BufferOffset, Buffer<glm::Mat4>, vk::DescriptorPool, vk::DescriptorSet, Count)>
+    mesh: Option<(BufferOffset, Count)>,
+    /// comes right after `mesh` in the world buffer
+    translucent_mesh: Option<(BufferOffset, Count)>,
+    /// kept around so they can be sorted again whenever the camera moves
+    translucent_vertices: Vec<ChunkVertex>,
+    resources: Option<ChunkResources>
+}
+
+/// the model uniform and descriptor set of a chunk, they only depend on where the chunk is
+/// so theyre made for the first mesh and then kept for every mesh after that
+#[derive(Debug)]
+pub struct ChunkResources {
+    model: Buffer<glm::Mat4>,
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set: vk::DescriptorSet
+}
+
+impl ChunkResources {
+    fn new(position: glm::IVec3) -> ChunkResources {
+        let model = Buffer::new(&[glm::Mat4::new_translation(&glm::vec3(position.x as f32, -position.y as f32, position.z as f32))], vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT).unwrap();
+
+        let descriptor_pool = engine::instance::create_descriptor_pool();
+        let descriptor_set = unsafe {
+            engine::instance::get_device().allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(descriptor_pool)
+                .set_layouts(&[engine::instance::get_descriptor_set_layout()])
+                .build()
+            ).unwrap()[0]
+        };
+
+        ChunkResources {
+            model,
+            descriptor_pool,
+            descriptor_set
+        }
+    }
+}
+
+impl Drop for ChunkResources {
+    /// the frame on the gpu cant be using them anymore at this point (see `Chunk::take_resources`)
+    fn drop(&mut self) {
+        unsafe {
+            // the set goes with the pool
+            engine::instance::get_device().destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
 }
 
 impl Chunk {
     pub const SIZE: u8 = 20;
+    pub const VOLUME: usize = (Chunk::SIZE as usize).pow(3);
 
-    pub fn new(position: glm::IVec3, chunk_gen: impl Fn(GlobalPos) -> Block) -> Chunk {
-        let mut blocks = HashMap::new();
+    pub fn new(position: glm::IVec3, chunk_gen: impl Fn(GlobalPos) -> BlockId) -> Chunk {
+        let blocks = PalettedContainer::from_fn(Chunk::VOLUME, |index| {
+            let local_pos = Chunk::local_pos(index);
 
-        for x in 0..Chunk::SIZE {
-            for y in 0..Chunk::SIZE {
-                for z in 0..Chunk::SIZE {
-                    let global_pos = position + glm::vec3(x as i32, y as i32, z as i32);
+            chunk_gen(position + glm::vec3(local_pos.x as i32, local_pos.y as i32, local_pos.z as i32))
+        });
 
-                    blocks.insert(glm::vec3(x as i8, y as i8, z as i8), chunk_gen(global_pos));
-                }
-            }
+        Chunk {
+            position,
+            blocks,
+            light: ChunkLight::new(),
+            buffer_offset: 0,
+            mesh: None,
+            translucent_mesh: None,
+            translucent_vertices: Vec::new(),
+            resources: None
         }
+    }
+
+    /// for chunks that were already generated once, like ones loaded from disk
+    pub fn from_blocks(position: glm::IVec3, blocks: PalettedContainer<BlockId>) -> Chunk {
+        assert_eq!(blocks.len(), Chunk::VOLUME, "chunk needs exactly {} blocks", Chunk::VOLUME);
 
         Chunk {
             position,
             blocks,
+            light: ChunkLight::new(),
             buffer_offset: 0,
-            mesh: None
+            mesh: None,
+            translucent_mesh: None,
+            translucent_vertices: Vec::new(),
+            resources: None
+        }
+    }
+
+    /// splits a global position into the position of the chunk its in and the local position inside of that chunk
+    pub fn locate(global_pos: GlobalPos) -> (GlobalPos, LocalPos) {
+        let size = Chunk::SIZE as i32;
+        let chunk_position = glm::vec3(global_pos.x.div_euclid(size), global_pos.y.div_euclid(size), global_pos.z.div_euclid(size)) * size;
+        let local_pos = global_pos - chunk_position;
+
+        (chunk_position, glm::vec3(local_pos.x as i8, local_pos.y as i8, local_pos.z as i8))
+    }
+
+    /// returns None if `local_pos` is outside of the chunk
+    pub fn get_block(&self, local_pos: LocalPos) -> Option<BlockId> {
+        Chunk::index(local_pos).map(|index| *self.blocks.get(index))
+    }
+
+    /// gives back the block that was there before, or None if `local_pos` is outside of the chunk
+    pub fn set_block(&mut self, local_pos: LocalPos, block: BlockId) -> Option<BlockId> {
+        let index = Chunk::index(local_pos)?;
+        let old_block = *self.blocks.get(index);
+        self.blocks.set(index, block);
+
+        Some(old_block)
+    }
+
+    /// returns None if `local_pos` is outside of the chunk
+    pub fn get_light(&self, local_pos: LocalPos, channel: LightChannel) -> Option<u8> {
+        Chunk::index(local_pos).map(|index| self.light.get(index, channel))
+    }
+
+    /// does nothing if `local_pos` is outside of the chunk
+    pub fn set_light(&mut self, local_pos: LocalPos, channel: LightChannel, level: u8) {
+        if let Some(index) = Chunk::index(local_pos) {
+            self.light.set(index, channel, level);
+        }
+    }
+
+    /// returns None if `local_pos` is outside of the chunk
+    fn index(local_pos: LocalPos) -> Option<usize> {
+        let size = Chunk::SIZE as i8;
+        if (0..size).contains(&local_pos.x) && (0..size).contains(&local_pos.y) && (0..size).contains(&local_pos.z) {
+            Some(local_pos.x as usize + local_pos.z as usize * Chunk::SIZE as usize + local_pos.y as usize * (Chunk::SIZE as usize).pow(2))
+        } else {
+            None
         }
     }
 
+    /// the other way around from the index blocks are stored at
+    pub fn local_pos(index: usize) -> LocalPos {
+        let size = Chunk::SIZE as usize;
+        glm::vec3((index % size) as i8, (index / size.pow(2)) as i8, ((index / size) % size) as i8)
+    }
+
     pub fn write_descriptor(&self, camera_buffer_info: vk::DescriptorBufferInfo, atlas_image_info: vk::DescriptorImageInfo) {
-        if let Some(mesh) = &self.mesh {
+        if let Some(resources) = self.resources.as_ref().filter(|_| self.mesh.is_some() || self.translucent_mesh.is_some()) {
             unsafe {
                 engine::instance::get_device().update_descriptor_sets(&[
                     vk::WriteDescriptorSet::builder()
-                        .dst_set(mesh.3)
+                        .dst_set(resources.descriptor_set)
                         .dst_binding(0)
                         .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                         .buffer_info(&[
@@ -59,7 +173,7 @@ impl Chunk {
                         ])
                         .build(),
                     vk::WriteDescriptorSet::builder()
-                        .dst_set(mesh.3)
+                        .dst_set(resources.descriptor_set)
                         .dst_binding(1)
                         .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
                         .image_info(&[
@@ -67,12 +181,12 @@ impl Chunk {
                         ])
                         .build(),
                     vk::WriteDescriptorSet::builder()
-                        .dst_set(mesh.3)
+                        .dst_set(resources.descriptor_set)
                         .dst_binding(2)
                         .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
                         .buffer_info(&[
                             vk::DescriptorBufferInfo::builder()
-                                .buffer(mesh.1.buffer())
+                                .buffer(resources.model.buffer())
                                 .range(size_of::<glm::Mat4>() as u64)
                                 .offset(0)
                                 .build()
@@ -83,204 +197,85 @@ impl Chunk {
         }
     }
 
+    /// the count is of indices into the quad index buffer of the world
     pub fn get_draw_info(&self) -> Option<(BufferOffset, vk::DescriptorSet, Count)> {
-        self.mesh.as_ref().map(|mesh| (mesh.0, mesh.3, mesh.4))
+        self.mesh.zip(self.resources.as_ref()).map(|(mesh, resources)| (mesh.0, resources.descriptor_set, mesh.1))
+    }
+
+    /// like `get_draw_info` for the translucent blocks, which have to be drawn after everything else
+    pub fn get_translucent_draw_info(&self) -> Option<(BufferOffset, vk::DescriptorSet, Count)> {
+        self.translucent_mesh.zip(self.resources.as_ref()).map(|(mesh, resources)| (mesh.0, resources.descriptor_set, mesh.1))
     }
 
     pub fn position(&self) -> glm::IVec3 {
         self.position
     }
 
+    pub fn blocks(&self) -> &PalettedContainer<BlockId> {
+        &self.blocks
+    }
+
     pub fn buffer_offset(&self) -> BufferOffset {
         self.buffer_offset
     }
-}
 
-pub fn build_mesh(chunk: *const Chunk, neighbour_chunks: [Option<*const Chunk>; 6], world_buffer: *const Buffer<Vertex>, offset: u64) {
-    let chunk = unsafe { &mut *chunk.cast_mut() };
-
-    let neighbour_chunks = unsafe {[
-        neighbour_chunks[0].map(|c| &*c),
-        neighbour_chunks[1].map(|c| &*c),
-        neighbour_chunks[2].map(|c| &*c),
-        neighbour_chunks[3].map(|c| &*c),
-        neighbour_chunks[4].map(|c| &*c),
-        neighbour_chunks[5].map(|c| &*c)
-    ]};
-
-    let mut vertices = Vec::new();
-
-    for x in 0..Chunk::SIZE {
-        for y in 0..Chunk::SIZE {
-            for z in 0..Chunk::SIZE {
-                let local_pos = glm::vec3(x as i8, y as i8, z as i8);
-
-                let current_block = chunk.blocks.get(&local_pos).unwrap();
-                if current_block.block_type == BlockType::Solid {
-                    let mut gen_west_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32), current_block.side_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(west_block) = chunk.blocks.get(&glm::vec3(x as i8 - 1, y as i8, z as i8)) {
-                        if west_block.block_type == BlockType::Air {
-                            gen_west_face();
-                        }
-                    } else {
-                        if let Some(west_chunk) = neighbour_chunks[0] {
-                            if west_chunk.blocks.get(&glm::vec3(Chunk::SIZE as i8 - 1, y as i8, z as i8)).unwrap().block_type == BlockType::Air {
-                                gen_west_face();
-                            }
-                        } else {
-                            gen_west_face();
-                        }
-                    }
-
-                    let mut gen_east_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(east_block) = chunk.blocks.get(&glm::vec3(x as i8 + 1, y as i8, z as i8)) {
-                        if east_block.block_type == BlockType::Air {
-                            gen_east_face();
-                        }
-                    } else {
-                        if let Some(east_chunk) = neighbour_chunks[1] {
-                            if east_chunk.blocks.get(&glm::vec3(0, y as i8, z as i8)).unwrap().block_type == BlockType::Air {
-                                gen_east_face();
-                            }
-                        } else {
-                            gen_east_face();
-                        }
-                    }
-
-                    let mut gen_up_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32), current_block.top_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32 - 1.0), current_block.top_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32 - 1.0), current_block.top_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32), current_block.top_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32), current_block.top_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32 - 1.0), current_block.top_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(up_block) = chunk.blocks.get(&glm::vec3(x as i8, y as i8 + 1, z as i8)) {
-                        if up_block.block_type == BlockType::Air {
-                            gen_up_face();
-                        }
-                    } else {
-                        if let Some(up_chunk) = neighbour_chunks[2] {
-                            if up_chunk.blocks.get(&glm::vec3(x as i8, 0, z as i8)).unwrap().block_type == BlockType::Air {
-                                gen_up_face();
-                            }
-                        } else {
-                            gen_up_face();
-                        }
-                    }
-
-                    let mut gen_down_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32 - 1.0), current_block.bottom_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32), current_block.bottom_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32), current_block.bottom_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32 - 1.0), current_block.bottom_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32 - 1.0), current_block.bottom_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32), current_block.bottom_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(down_block) = chunk.blocks.get(&glm::vec3(x as i8, y as i8 - 1, z as i8)) {
-                        if down_block.block_type == BlockType::Air {
-                            gen_down_face();
-                        }
-                    } else {
-                        if let Some(down_chunk) = neighbour_chunks[3] {
-                            if down_chunk.blocks.get(&glm::vec3(x as i8, Chunk::SIZE as i8 - 1, z as i8)).unwrap().block_type == BlockType::Air {
-                                gen_down_face();
-                            }
-                        } else {
-                            gen_down_face();
-                        }
-                    }
-
-                    let mut gen_north_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32 - 1.0), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(north_block) = chunk.blocks.get(&glm::vec3(x as i8, y as i8, z as i8 - 1)) {
-                        if north_block.block_type == BlockType::Air {
-                            gen_north_face();
-                        }
-                    } else {
-                        if let Some(north_chunk) = neighbour_chunks[4] {
-                            if north_chunk.blocks.get(&glm::vec3(x as i8, y as i8, Chunk::SIZE as i8 - 1)).unwrap().block_type == BlockType::Air {
-                                gen_north_face();
-                            }
-                        } else {
-                            gen_north_face();
-                        }
-                    }
-
-                    let mut gen_south_face = || {
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.0, 0.1)));
-
-                        vertices.push(Vertex::new(glm::vec3(x as f32, y as f32, z as f32), current_block.side_uv + glm::vec2(0.0, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32, z as f32), current_block.side_uv + glm::vec2(0.1, 0.0)));
-                        vertices.push(Vertex::new(glm::vec3(x as f32 + 1.0, y as f32 + 1.0, z as f32), current_block.side_uv + glm::vec2(0.1, 0.1)));
-                    };
-                    if let Some(south_block) = chunk.blocks.get(&glm::vec3(x as i8, y as i8, z as i8 + 1)) {
-                        if south_block.block_type == BlockType::Air {
-                            gen_south_face();
-                        }
-                    } else {
-                        if let Some(down_chunk) = neighbour_chunks[5] {
-                            if down_chunk.blocks.get(&glm::vec3(x as i8, y as i8, 0)).unwrap().block_type == BlockType::Air {
-                                gen_south_face();
-                            }
-                        } else {
-                            gen_south_face();
-                        }
-                    }
-                }
-            }
-        }
-    }
+    /// copy of the blocks and light without the mesh, for handing the chunk to another thread
+    pub fn snapshot(&self) -> Chunk {
+        let mut snapshot = Chunk::from_blocks(self.position, self.blocks.clone());
+        snapshot.light = self.light.clone();
 
-    let world_buffer = unsafe { &*world_buffer };
-    let ptr = world_buffer.map(offset, World::MAX_VERTICES_PER_CHUNK_BYTES);
-    unsafe {
-        ptr.copy_from_nonoverlapping(vertices.as_ptr(), vertices.len());
+        snapshot
     }
-    world_buffer.unmap();
-    
-    let model = Buffer::new(&[glm::Mat4::new_translation(&glm::vec3(chunk.position.x as f32, -chunk.position.y as f32, chunk.position.z as f32))], vk::BufferUsageFlags::UNIFORM_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT).unwrap();
 
-    chunk.buffer_offset = offset;
+    /// writes a mesh from `mesher::build_mesh` into the chunks part of the world buffer, has to happen on the main thread
+    ///
+    /// the translucent part goes in the order `eye` (in block space) would see it, see `sort_translucent`
+    pub fn upload_mesh(&mut self, mesh: &ChunkMesh, world_buffer: &Buffer<ChunkVertex>, offset: BufferOffset, eye: glm::Vec3) {
+        let vertices = mesh.vertices.as_slice();
 
-    if vertices.len() > 0 {    
-        let descriptor_pool = engine::instance::create_descriptor_pool();
-        let descriptor_set = unsafe {
-            engine::instance::get_device().allocate_descriptor_sets(
-                &vk::DescriptorSetAllocateInfo::builder()
-                .descriptor_pool(descriptor_pool)
-                .set_layouts(&[engine::instance::get_descriptor_set_layout()])
-                .build()
-            ).unwrap()[0]
+        assert!((size_of_val(vertices) + size_of_val(mesh.translucent.as_slice())) as u64 <= World::MAX_VERTICES_PER_CHUNK_BYTES, "chunk mesh doesnt fit into its part of the world buffer");
+
+        let ptr = world_buffer.map(offset, World::MAX_VERTICES_PER_CHUNK_BYTES);
+        unsafe {
+            ptr.copy_from_nonoverlapping(vertices.as_ptr(), vertices.len());
+        }
+        world_buffer.unmap();
+
+        self.buffer_offset = offset;
+        // everything in the chunk could have been removed since the last mesh
+        self.mesh = (!vertices.is_empty()).then_some((offset, mesh.index_count()));
+        self.translucent_mesh = (!mesh.translucent.is_empty()).then_some((offset + size_of_val(vertices) as u64, mesh.translucent_index_count()));
+        self.translucent_vertices = mesh.translucent.clone();
+        self.sort_translucent(world_buffer, eye);
+
+        if !mesh.is_empty() && self.resources.is_none() {
+            self.resources = Some(ChunkResources::new(self.position));
+        }
+    }
+
+    /// puts the translucent quads in order from the furthest away from `eye` (in block space) to the closest
+    /// and writes them to the world buffer again
+    pub fn sort_translucent(&mut self, world_buffer: &Buffer<ChunkVertex>, eye: glm::Vec3) {
+        let Some((offset, _)) = self.translucent_mesh else {
+            return;
         };
-        
-        chunk.mesh = Some((offset, model, descriptor_pool, descriptor_set, vertices.len() as u64));
+
+        // the vertices go from z - 1 to z (see `raycast::to_block_space`)
+        let position = self.position;
+        mesher::sort_back_to_front(&mut self.translucent_vertices, eye - glm::vec3(position.x as f32, position.y as f32, position.z as f32 + 1.0));
+
+        let ptr = world_buffer.map(offset, size_of_val(self.translucent_vertices.as_slice()) as u64);
+        unsafe {
+            ptr.copy_from_nonoverlapping(self.translucent_vertices.as_ptr(), self.translucent_vertices.len());
+        }
+        world_buffer.unmap();
+    }
+
+    /// for unloading, the last frame could still be drawing the chunk so the resources
+    /// have to be kept around until its done
+    pub fn take_resources(&mut self) -> Option<ChunkResources> {
+        self.mesh = None;
+        self.translucent_mesh = None;
+        self.resources.take()
     }
 }
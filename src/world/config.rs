@@ -0,0 +1,224 @@
+use std::{fmt::{self, Display}, path::Path};
+use crate::json::{JsonValue, ParseError};
+use super::generation::{heightmap::HeightmapSettings, biome::BiomeSettings, caves::{CaveSettings, OreSettings}};
+
+/// everything needed to create a new world, anything left out of the file uses the default
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldConfig {
+    pub seed: u32,
+    pub heightmap: HeightmapSettings,
+    pub biomes: BiomeSettings,
+    pub caves: CaveSettings,
+    pub ores: Vec<OreSettings>
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Json(ParseError),
+    Invalid(String)
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "couldn't read world config: {e}"),
+            ConfigError::Json(e) => write!(f, "world config isn't valid json: {e}"),
+            ConfigError::Invalid(message) => write!(f, "invalid world config: {message}")
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for WorldConfig {
+    fn default() -> WorldConfig {
+        WorldConfig {
+            seed: 123,
+            heightmap: HeightmapSettings::default(),
+            biomes: BiomeSettings::default(),
+            caves: CaveSettings::default(),
+            ores: OreSettings::default_ores()
+        }
+    }
+}
+
+impl WorldConfig {
+    pub fn load(path: impl AsRef<Path>) -> Result<WorldConfig, ConfigError> {
+        let text = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+
+        WorldConfig::from_json(&JsonValue::parse(&text).map_err(ConfigError::Json)?)
+    }
+
+    pub fn from_json(json: &JsonValue) -> Result<WorldConfig, ConfigError> {
+        let default = WorldConfig::default();
+
+        let heightmap = match json.get("heightmap") {
+            Some(heightmap) => heightmap_from_json(heightmap)?,
+            None => default.heightmap
+        };
+
+        let biomes = match json.get("biomes") {
+            Some(biomes) => biomes_from_json(biomes)?,
+            None => default.biomes
+        };
+
+        let caves = match json.get("caves") {
+            Some(caves) => caves_from_json(caves)?,
+            None => default.caves
+        };
+
+        let ores = match json.get("ores") {
+            Some(ores) => ores.as_array()
+                .ok_or_else(|| ConfigError::Invalid("\"ores\" should be an array".to_string()))?
+                .iter()
+                .map(ore_from_json)
+                .collect::<Result<Vec<_>, _>>()?,
+            None => default.ores
+        };
+
+        Ok(WorldConfig {
+            seed: u32_field(json, "seed", default.seed)?,
+            heightmap,
+            biomes,
+            caves,
+            ores
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("seed".to_string(), JsonValue::Number(self.seed as f64)),
+            ("heightmap".to_string(), heightmap_to_json(&self.heightmap)),
+            ("biomes".to_string(), biomes_to_json(&self.biomes)),
+            ("caves".to_string(), caves_to_json(&self.caves)),
+            ("ores".to_string(), JsonValue::Array(self.ores.iter().map(ore_to_json).collect()))
+        ])
+    }
+}
+
+fn heightmap_from_json(json: &JsonValue) -> Result<HeightmapSettings, ConfigError> {
+    let default = HeightmapSettings::default();
+
+    Ok(HeightmapSettings {
+        octaves: u32_field(json, "octaves", default.octaves)?,
+        frequency: f64_field(json, "frequency", default.frequency)?,
+        lacunarity: f64_field(json, "lacunarity", default.lacunarity)?,
+        persistence: f64_field(json, "persistence", default.persistence)?,
+        base_height: i32_field(json, "base_height", default.base_height)?,
+        amplitude: f64_field(json, "amplitude", default.amplitude)?,
+        warp_strength: f64_field(json, "warp_strength", default.warp_strength)?,
+        warp_frequency: f64_field(json, "warp_frequency", default.warp_frequency)?
+    })
+}
+
+fn heightmap_to_json(settings: &HeightmapSettings) -> JsonValue {
+    JsonValue::Object(vec![
+        ("octaves".to_string(), JsonValue::Number(settings.octaves as f64)),
+        ("frequency".to_string(), JsonValue::Number(settings.frequency)),
+        ("lacunarity".to_string(), JsonValue::Number(settings.lacunarity)),
+        ("persistence".to_string(), JsonValue::Number(settings.persistence)),
+        ("base_height".to_string(), JsonValue::Number(settings.base_height as f64)),
+        ("amplitude".to_string(), JsonValue::Number(settings.amplitude)),
+        ("warp_strength".to_string(), JsonValue::Number(settings.warp_strength)),
+        ("warp_frequency".to_string(), JsonValue::Number(settings.warp_frequency))
+    ])
+}
+
+fn biomes_from_json(json: &JsonValue) -> Result<BiomeSettings, ConfigError> {
+    let default = BiomeSettings::default();
+
+    Ok(BiomeSettings {
+        temperature_frequency: f64_field(json, "temperature_frequency", default.temperature_frequency)?,
+        humidity_frequency: f64_field(json, "humidity_frequency", default.humidity_frequency)?,
+        blend: f64_field(json, "blend", default.blend)?
+    })
+}
+
+fn biomes_to_json(settings: &BiomeSettings) -> JsonValue {
+    JsonValue::Object(vec![
+        ("temperature_frequency".to_string(), JsonValue::Number(settings.temperature_frequency)),
+        ("humidity_frequency".to_string(), JsonValue::Number(settings.humidity_frequency)),
+        ("blend".to_string(), JsonValue::Number(settings.blend))
+    ])
+}
+
+fn caves_from_json(json: &JsonValue) -> Result<CaveSettings, ConfigError> {
+    let default = CaveSettings::default();
+
+    Ok(CaveSettings {
+        cheese_frequency: f64_field(json, "cheese_frequency", default.cheese_frequency)?,
+        cheese_threshold: f64_field(json, "cheese_threshold", default.cheese_threshold)?,
+        spaghetti_frequency: f64_field(json, "spaghetti_frequency", default.spaghetti_frequency)?,
+        spaghetti_thickness: f64_field(json, "spaghetti_thickness", default.spaghetti_thickness)?,
+        surface_margin: i32_field(json, "surface_margin", default.surface_margin)?
+    })
+}
+
+fn caves_to_json(settings: &CaveSettings) -> JsonValue {
+    JsonValue::Object(vec![
+        ("cheese_frequency".to_string(), JsonValue::Number(settings.cheese_frequency)),
+        ("cheese_threshold".to_string(), JsonValue::Number(settings.cheese_threshold)),
+        ("spaghetti_frequency".to_string(), JsonValue::Number(settings.spaghetti_frequency)),
+        ("spaghetti_thickness".to_string(), JsonValue::Number(settings.spaghetti_thickness)),
+        ("surface_margin".to_string(), JsonValue::Number(settings.surface_margin as f64))
+    ])
+}
+
+/// every field is required here since there isnt a sensible default ore
+fn ore_from_json(json: &JsonValue) -> Result<OreSettings, ConfigError> {
+    let block = json.get("block")
+        .and_then(|block| block.as_str())
+        .ok_or_else(|| ConfigError::Invalid("every ore needs a \"block\" dev name".to_string()))?
+        .to_string();
+
+    for key in ["veins_per_chunk", "vein_size", "min_y", "max_y"] {
+        if json.get(key).is_none() {
+            return Err(ConfigError::Invalid(format!("ore \"{block}\" is missing \"{key}\"")));
+        }
+    }
+
+    Ok(OreSettings {
+        veins_per_chunk: f64_field(json, "veins_per_chunk", 0.0)?,
+        vein_size: u32_field(json, "vein_size", 0)?,
+        min_y: i32_field(json, "min_y", 0)?,
+        max_y: i32_field(json, "max_y", 0)?,
+        block
+    })
+}
+
+fn ore_to_json(settings: &OreSettings) -> JsonValue {
+    JsonValue::Object(vec![
+        ("block".to_string(), JsonValue::String(settings.block.clone())),
+        ("veins_per_chunk".to_string(), JsonValue::Number(settings.veins_per_chunk)),
+        ("vein_size".to_string(), JsonValue::Number(settings.vein_size as f64)),
+        ("min_y".to_string(), JsonValue::Number(settings.min_y as f64)),
+        ("max_y".to_string(), JsonValue::Number(settings.max_y as f64))
+    ])
+}
+
+/// number field that falls back to `default` if its missing
+fn f64_field(json: &JsonValue, key: &str, default: f64) -> Result<f64, ConfigError> {
+    match json.get(key) {
+        Some(value) => value.as_f64().ok_or_else(|| ConfigError::Invalid(format!("\"{key}\" should be a number but is a {}", value.type_name()))),
+        None => Ok(default)
+    }
+}
+
+fn u32_field(json: &JsonValue, key: &str, default: u32) -> Result<u32, ConfigError> {
+    let value = f64_field(json, key, default as f64)?;
+    if value.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&value) {
+        Ok(value as u32)
+    } else {
+        Err(ConfigError::Invalid(format!("\"{key}\" should be a whole number from 0 to {}", u32::MAX)))
+    }
+}
+
+fn i32_field(json: &JsonValue, key: &str, default: i32) -> Result<i32, ConfigError> {
+    let value = f64_field(json, key, default as f64)?;
+    if value.fract() == 0.0 && (i32::MIN as f64..=i32::MAX as f64).contains(&value) {
+        Ok(value as i32)
+    } else {
+        Err(ConfigError::Invalid(format!("\"{key}\" should be a whole number")))
+    }
+}
@@ -0,0 +1,278 @@
+use std::{collections::HashMap, fmt::{self, Display}, path::Path};
+use crate::json::{JsonValue, ParseError};
+use super::{block::{Block, BlockType}, light::MAX_LIGHT, registry::BlockRegistry};
+
+/// the atlas is a grid of `ATLAS_TILES` x `ATLAS_TILES` textures, so one tile is 0.1 uv wide
+pub const ATLAS_TILES: u32 = 10;
+
+pub const MAX_LIGHT_EMISSION: u8 = MAX_LIGHT;
+
+#[derive(Debug)]
+pub enum DefinitionError {
+    Io(std::io::Error),
+    Json(ParseError),
+    /// `context` says where in the file it happened, e.g. `block "stone"`
+    Invalid {
+        context: String,
+        message: String
+    },
+    UnknownTexture {
+        block: String,
+        texture: String
+    },
+    DuplicateBlock(String)
+}
+
+impl Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefinitionError::Io(e) => write!(f, "couldn't read block definitions: {e}"),
+            DefinitionError::Json(e) => write!(f, "block definitions aren't valid json: {e}"),
+            DefinitionError::Invalid { context, message } => write!(f, "{context}: {message}"),
+            DefinitionError::UnknownTexture { block, texture } => write!(f, "block \"{block}\" uses texture \"{texture}\" which isn't in \"textures\""),
+            DefinitionError::DuplicateBlock(dev_name) => write!(f, "block \"{dev_name}\" is defined more than once")
+        }
+    }
+}
+
+impl std::error::Error for DefinitionError {}
+
+/// reads a block definitions file and registers every block in it after air
+///
+/// ```json
+/// {
+///     "textures": {
+///         "grass_top": [0, 0]
+///     },
+///     "blocks": [
+///         {
+///             "name": "Grass Block",
+///             "dev_name": "grass_block",
+///             "type": "solid",
+///             "textures": { "top": "grass_top", "side": "grass_side", "bottom": "dirt" },
+///             "transparent": false,
+///             "light_emission": 0,
+///             "light_color": [255, 255, 255],
+///             "opacity": 15
+///         }
+///     ]
+/// }
+/// ```
+///
+/// textures are `[column, row]` tiles in the atlas with `[0, 0]` being the bottom left.
+/// `"textures": { "all": "stone" }` can be used instead of listing every face.
+/// `"light_color"` is the red, green and blue (0 to 255) of the light a block gives off, white if its left out.
+/// `"type"` is one of `"air"`, `"solid"`, `"cutout"` (alpha tested) or `"translucent"` (blended), anything but solid is transparent
+/// unless `"transparent"` says otherwise. without `"opacity"` transparent blocks let all light through and the others none
+pub fn load_block_definitions(path: impl AsRef<Path>) -> Result<BlockRegistry, DefinitionError> {
+    let text = std::fs::read_to_string(path).map_err(DefinitionError::Io)?;
+
+    parse_block_definitions(&text)
+}
+
+pub fn parse_block_definitions(text: &str) -> Result<BlockRegistry, DefinitionError> {
+    let root = JsonValue::parse(text).map_err(DefinitionError::Json)?;
+    check_fields(&root, "definitions file", &["textures", "blocks"])?;
+
+    let mut textures = HashMap::new();
+    for (name, tile) in required(&root, "definitions file", "textures")?.as_object().ok_or_else(|| invalid("definitions file", "\"textures\" should be an object"))? {
+        let context = format!("texture \"{name}\"");
+        let tile = tile.as_array()
+            .filter(|tile| tile.len() == 2)
+            .and_then(|tile| Some((tile[0].as_i64()?, tile[1].as_i64()?)))
+            .ok_or_else(|| invalid(&context, "should be a [column, row] pair"))?;
+
+        if !(0..ATLAS_TILES as i64).contains(&tile.0) || !(0..ATLAS_TILES as i64).contains(&tile.1) {
+            return Err(invalid(&context, format!("tile [{}, {}] is outside of the {ATLAS_TILES}x{ATLAS_TILES} atlas", tile.0, tile.1)));
+        }
+
+        // the json parser already errors on duplicate keys so a texture cant be defined twice
+        let uv = glm::vec2(tile.0 as f32 / ATLAS_TILES as f32, tile.1 as f32 / ATLAS_TILES as f32);
+        textures.insert(name.as_str(), uv);
+    }
+
+    let mut registry = BlockRegistry::new();
+    let blocks = required(&root, "definitions file", "blocks")?.as_array().ok_or_else(|| invalid("definitions file", "\"blocks\" should be an array"))?;
+    for (i, definition) in blocks.iter().enumerate() {
+        let block = parse_block(definition, i, &textures)?;
+
+        if registry.id(&block.dev_name).is_some() {
+            return Err(DefinitionError::DuplicateBlock(block.dev_name));
+        }
+        registry.register(block);
+    }
+
+    Ok(registry)
+}
+
+fn parse_block(definition: &JsonValue, index: usize, textures: &HashMap<&str, glm::Vec2>) -> Result<Block, DefinitionError> {
+    // dev_name isnt known yet so fall back to the index for errors
+    let context = format!("block #{index}");
+    check_fields(definition, &context, &["name", "dev_name", "type", "textures", "transparent", "light_emission", "light_color", "opacity"])?;
+
+    let dev_name = required_str(definition, &context, "dev_name")?.to_string();
+    let context = format!("block \"{dev_name}\"");
+
+    if dev_name.is_empty() || !dev_name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+        return Err(invalid(&context, "\"dev_name\" should only contain lowercase letters, digits and underscores"));
+    }
+
+    let name = required_str(definition, &context, "name")?.to_string();
+
+    let block_type = match required_str(definition, &context, "type")? {
+        "air" => BlockType::Air,
+        "solid" => BlockType::Solid,
+        "cutout" => BlockType::Cutout,
+        "translucent" => BlockType::Translucent,
+        other => return Err(invalid(&context, format!("unknown type \"{other}\", expected \"air\", \"solid\", \"cutout\" or \"translucent\"")))
+    };
+
+    let face_textures = required(definition, &context, "textures")?;
+    check_fields(face_textures, &context, &["all", "top", "side", "bottom"])?;
+    let face_uv = |face: &str| -> Result<glm::Vec2, DefinitionError> {
+        let texture = match face_textures.get(face).or(face_textures.get("all")) {
+            Some(texture) => texture.as_str().ok_or_else(|| invalid(&context, format!("texture for \"{face}\" should be a string")))?,
+            None => return Err(invalid(&context, format!("no texture for \"{face}\" and no \"all\" texture")))
+        };
+
+        textures.get(texture).copied().ok_or_else(|| DefinitionError::UnknownTexture {
+            block: dev_name.clone(),
+            texture: texture.to_string()
+        })
+    };
+    let top_uv = face_uv("top")?;
+    let side_uv = face_uv("side")?;
+    let bottom_uv = face_uv("bottom")?;
+
+    let mut block = Block::new(name, &dev_name, block_type, top_uv, side_uv, bottom_uv);
+
+    if let Some(transparent) = definition.get("transparent") {
+        block.transparent = transparent.as_bool().ok_or_else(|| invalid(&context, "\"transparent\" should be a bool"))?;
+    }
+
+    if let Some(light_emission) = definition.get("light_emission") {
+        block.light_emission = light_emission.as_i64()
+            .filter(|l| (0..=MAX_LIGHT_EMISSION as i64).contains(l))
+            .ok_or_else(|| invalid(&context, format!("\"light_emission\" should be a whole number from 0 to {MAX_LIGHT_EMISSION}")))? as u8;
+    }
+
+    if let Some(light_color) = definition.get("light_color") {
+        block.light_color = light_color.as_array()
+            .filter(|color| color.len() == 3)
+            .and_then(|color| Some([color[0].as_i64()?, color[1].as_i64()?, color[2].as_i64()?]))
+            .filter(|color| color.iter().all(|c| (0..=255).contains(c)))
+            .ok_or_else(|| invalid(&context, "\"light_color\" should be a [red, green, blue] array of whole numbers from 0 to 255"))?
+            .map(|c| c as u8);
+    }
+
+    block.opacity = if block.transparent { 0 } else { MAX_LIGHT };
+    if let Some(opacity) = definition.get("opacity") {
+        block.opacity = opacity.as_i64()
+            .filter(|o| (0..=MAX_LIGHT as i64).contains(o))
+            .ok_or_else(|| invalid(&context, format!("\"opacity\" should be a whole number from 0 to {MAX_LIGHT}")))? as u8;
+    }
+
+    Ok(block)
+}
+
+fn invalid(context: &str, message: impl ToString) -> DefinitionError {
+    DefinitionError::Invalid {
+        context: context.to_string(),
+        message: message.to_string()
+    }
+}
+
+/// errors on typos instead of silently ignoring them
+fn check_fields(value: &JsonValue, context: &str, allowed: &[&str]) -> Result<(), DefinitionError> {
+    let object = value.as_object().ok_or_else(|| invalid(context, format!("expected an object but found {}", value.type_name())))?;
+
+    match object.iter().find(|(key, _)| !allowed.contains(&key.as_str())) {
+        Some((key, _)) => Err(invalid(context, format!("unknown field \"{key}\", expected one of {allowed:?}"))),
+        None => Ok(())
+    }
+}
+
+fn required<'a>(value: &'a JsonValue, context: &str, field: &str) -> Result<&'a JsonValue, DefinitionError> {
+    value.get(field).ok_or_else(|| invalid(context, format!("missing field \"{field}\"")))
+}
+
+fn required_str<'a>(value: &'a JsonValue, context: &str, field: &str) -> Result<&'a str, DefinitionError> {
+    required(value, context, field)?.as_str().ok_or_else(|| invalid(context, format!("\"{field}\" should be a string")))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::block::BlockType;
+    use super::{load_block_definitions, parse_block_definitions, DefinitionError};
+
+    fn definitions(blocks: &str) -> String {
+        format!(r#"{{ "textures": {{ "stone": [3, 0], "dirt": [2, 0] }}, "blocks": [{blocks}] }}"#)
+    }
+
+    #[test]
+    fn parses_blocks() {
+        let registry = parse_block_definitions(&definitions(r#"
+            { "name": "Stone", "dev_name": "stone", "type": "solid", "textures": { "all": "stone" } },
+            { "name": "Lamp", "dev_name": "lamp", "type": "solid", "textures": { "top": "stone", "side": "dirt", "bottom": "dirt" }, "light_emission": 15 },
+            { "name": "Lava", "dev_name": "lava", "type": "solid", "textures": { "all": "stone" }, "light_emission": 15, "light_color": [255, 102, 0] },
+            { "name": "Glass", "dev_name": "glass", "type": "solid", "textures": { "all": "stone" }, "transparent": true },
+            { "name": "Leaves", "dev_name": "leaves", "type": "cutout", "textures": { "all": "dirt" }, "opacity": 2 },
+            { "name": "Water", "dev_name": "water", "type": "translucent", "textures": { "all": "stone" } }
+        "#)).unwrap();
+
+        assert_eq!(registry.len(), 7);
+        assert_eq!(registry.get_by_dev_name("stone").unwrap().side_uv, glm::vec2(0.3, 0.0));
+        assert_eq!(registry.get_by_dev_name("lamp").unwrap().side_uv, glm::vec2(0.2, 0.0));
+        assert_eq!(registry.get_by_dev_name("lamp").unwrap().emission(), [15, 15, 15]);
+        assert_eq!(registry.get_by_dev_name("lava").unwrap().emission(), [15, 6, 0]);
+        assert_eq!(registry.get_by_dev_name("stone").unwrap().emission(), [0, 0, 0]);
+
+        let opacities = ["air", "stone", "glass", "leaves", "water"].map(|dev_name| registry.get_by_dev_name(dev_name).unwrap().opacity);
+        assert_eq!(opacities, [0, 15, 0, 2, 0]);
+        let block_types = ["stone", "leaves", "water"].map(|dev_name| registry.get_by_dev_name(dev_name).unwrap().block_type);
+        assert_eq!(block_types, [BlockType::Solid, BlockType::Cutout, BlockType::Translucent]);
+    }
+
+    #[test]
+    fn shipped_definitions() {
+        let registry = load_block_definitions("blocks.json").unwrap();
+        let block_types = ["stone", "oak_leaves", "dandelion", "poppy", "glass", "water", "ice"].map(|dev_name| registry.get_by_dev_name(dev_name).unwrap().block_type);
+        assert_eq!(block_types, [BlockType::Solid, BlockType::Cutout, BlockType::Cutout, BlockType::Cutout, BlockType::Translucent, BlockType::Translucent, BlockType::Translucent]);
+        let emissions = ["lamp", "lava", "crystal"].map(|dev_name| registry.get_by_dev_name(dev_name).unwrap().emission());
+        assert_eq!(emissions, [[15, 13, 9], [15, 6, 0], [6, 4, 12]]);
+    }
+
+    #[test]
+    fn unknown_texture() {
+        let error = parse_block_definitions(&definitions(r#"{ "name": "Stone", "dev_name": "stone", "type": "solid", "textures": { "all": "granite" } }"#)).unwrap_err();
+        assert!(matches!(&error, DefinitionError::UnknownTexture { block, texture } if block == "stone" && texture == "granite"), "{error}");
+    }
+
+    #[test]
+    fn duplicate_dev_name() {
+        let error = parse_block_definitions(&definitions(r#"
+            { "name": "Stone", "dev_name": "stone", "type": "solid", "textures": { "all": "stone" } },
+            { "name": "Other Stone", "dev_name": "stone", "type": "solid", "textures": { "all": "dirt" } }
+        "#)).unwrap_err();
+        assert!(matches!(&error, DefinitionError::DuplicateBlock(dev_name) if dev_name == "stone"), "{error}");
+
+        // air is registered before anything from the file
+        let error = parse_block_definitions(&definitions(r#"{ "name": "Air", "dev_name": "air", "type": "air", "textures": { "all": "stone" } }"#)).unwrap_err();
+        assert!(matches!(&error, DefinitionError::DuplicateBlock(dev_name) if dev_name == "air"), "{error}");
+    }
+
+    #[test]
+    fn duplicate_texture() {
+        let error = parse_block_definitions(r#"{ "textures": { "stone": [3, 0], "stone": [2, 0] }, "blocks": [] }"#).unwrap_err();
+        assert!(matches!(&error, DefinitionError::Json(e) if e.message == "duplicate key \"stone\""), "{error}");
+    }
+
+    #[test]
+    fn unknown_field() {
+        let error = parse_block_definitions(&definitions(r#"{ "name": "Stone", "dev_name": "stone", "type": "solid", "textures": { "all": "stone" }, "hardness": 2 }"#)).unwrap_err();
+        assert_eq!(error.to_string(), "block #0: unknown field \"hardness\", expected one of [\"name\", \"dev_name\", \"type\", \"textures\", \"transparent\", \"light_emission\", \"light_color\", \"opacity\"]");
+
+        let error = parse_block_definitions(&definitions(r#"{ "name": "Stone", "dev_name": "stone", "type": "solid", "textures": { "front": "stone" } }"#)).unwrap_err();
+        assert!(matches!(&error, DefinitionError::Invalid { context, .. } if context == "block \"stone\""), "{error}");
+    }
+}
@@ -0,0 +1,335 @@
+use std::sync::OnceLock;
+use noise::{NoiseFn, Perlin};
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}, registry::BlockRegistry, config::WorldConfig};
+use super::{TerrainGenerator, heightmap::{HeightmapNoise, HeightmapSettings}, caves::{self, CaveNoise, CaveSettings, OreSettings}, decoration::{Feature, Tree, Boulder, Flower}, random::Random};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Biome {
+    Plains,
+    Desert,
+    Tundra,
+    Forest,
+    Mountains
+}
+
+impl Biome {
+    pub const ALL: [Biome; 5] = [Biome::Plains, Biome::Desert, Biome::Tundra, Biome::Forest, Biome::Mountains];
+
+    /// (temperature, humidity) the biome likes the most, each column picks the closest one
+    fn climate(self) -> (f64, f64) {
+        match self {
+            Biome::Plains => (0.0, 0.0),
+            Biome::Desert => (0.5, -0.4),
+            Biome::Tundra => (-0.5, 0.1),
+            Biome::Forest => (0.2, 0.45),
+            Biome::Mountains => (-0.25, -0.45)
+        }
+    }
+
+    /// (height offset, amplitude multiplier) applied on top of `HeightmapSettings`
+    fn height_profile(self) -> (f64, f64) {
+        match self {
+            Biome::Plains => (0.0, 0.4),
+            Biome::Desert => (2.0, 0.3),
+            Biome::Tundra => (4.0, 0.6),
+            Biome::Forest => (2.0, 0.8),
+            Biome::Mountains => (24.0, 2.5)
+        }
+    }
+
+    /// dev names of the (surface, subsurface) blocks
+    fn block_names(self) -> (&'static str, &'static str) {
+        match self {
+            Biome::Plains | Biome::Forest => ("grass_block", "dirt"),
+            Biome::Desert => ("sand", "sand"),
+            Biome::Tundra => ("snow", "dirt"),
+            Biome::Mountains => ("stone", "stone")
+        }
+    }
+
+    /// average [trees, flowers, boulders] per chunk
+    fn decoration_density(self) -> [f64; 3] {
+        match self {
+            Biome::Plains => [0.3, 6.0, 0.2],
+            Biome::Desert => [0.0, 0.0, 0.0],
+            Biome::Tundra => [0.5, 0.0, 0.0],
+            Biome::Forest => [8.0, 2.0, 0.0],
+            Biome::Mountains => [0.0, 0.0, 1.0]
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiomeSettings {
+    pub temperature_frequency: f64,
+    pub humidity_frequency: f64,
+    /// how wide the transitions are in climate space, bigger means smoother and wider borders
+    pub blend: f64
+}
+
+impl Default for BiomeSettings {
+    fn default() -> BiomeSettings {
+        BiomeSettings {
+            temperature_frequency: 1.0 / 512.0,
+            humidity_frequency: 1.0 / 384.0,
+            blend: 0.25
+        }
+    }
+}
+
+/// temperature and humidity noise for one seed
+pub struct BiomeMap {
+    settings: BiomeSettings,
+    temperature: Perlin,
+    humidity: Perlin
+}
+
+impl BiomeMap {
+    pub fn new(settings: BiomeSettings, seed: u32) -> BiomeMap {
+        BiomeMap {
+            settings,
+            // offset so they dont line up with the heightmap noise
+            temperature: Perlin::new(seed.wrapping_add(100)),
+            humidity: Perlin::new(seed.wrapping_add(101))
+        }
+    }
+
+    pub fn climate(&self, x: i32, z: i32) -> (f64, f64) {
+        (
+            self.temperature.get([x as f64 * self.settings.temperature_frequency, z as f64 * self.settings.temperature_frequency]),
+            self.humidity.get([x as f64 * self.settings.humidity_frequency, z as f64 * self.settings.humidity_frequency])
+        )
+    }
+
+    pub fn biome(&self, x: i32, z: i32) -> Biome {
+        strongest(&self.weights(x, z))
+    }
+
+    /// how much every biome in `Biome::ALL` contributes to the column, adds up to 1
+    pub fn weights(&self, x: i32, z: i32) -> [f64; 5] {
+        let (temperature, humidity) = self.climate(x, z);
+
+        let distances = Biome::ALL.map(|biome| {
+            let (t, h) = biome.climate();
+            (temperature - t).powi(2) + (humidity - h).powi(2)
+        });
+        // relative to the closest biome so far away biomes dont underflow everything to 0
+        let closest = distances.iter().copied().fold(f64::MAX, f64::min);
+        let weights = distances.map(|d| (-(d - closest) / self.settings.blend.powi(2)).exp());
+
+        let total = weights.iter().sum::<f64>();
+        weights.map(|w| w / total)
+    }
+}
+
+fn strongest(weights: &[f64; 5]) -> Biome {
+    Biome::ALL[(0..Biome::ALL.len()).max_by(|a, b| weights[*a].total_cmp(&weights[*b])).unwrap()]
+}
+
+/// fractal heightmap where every column blends the height profile of the biomes around it
+/// and uses the blocks of the strongest one, then caves get carved out and ores get scattered around
+///
+/// trees, flowers and boulders get placed on top afterwards by `decorate`
+pub struct BiomeGenerator {
+    heightmap: HeightmapSettings,
+    biomes: BiomeSettings,
+    caves: CaveSettings,
+    ores: Vec<(BlockId, OreSettings)>,
+    /// (surface, subsurface) in the same order as `Biome::ALL`
+    blocks: [(BlockId, BlockId); 5],
+    stone_block: BlockId,
+    subsurface_depth: i32,
+    /// [trees, flowers, boulders], same order as `Biome::decoration_density`
+    features: [Box<dyn Feature>; 3],
+    /// every block decorations use from the lowest to the highest priority
+    decoration_blocks: Vec<BlockId>,
+    /// noise for the first seed `surface_height` got, the lighting asks for one column at a time
+    surface_noise: OnceLock<(u32, HeightmapNoise, BiomeMap)>
+}
+
+impl BiomeGenerator {
+    /// panics if the registry is missing one of the blocks the biomes or ores use
+    pub fn new(config: &WorldConfig, registry: &BlockRegistry) -> BiomeGenerator {
+        let required_block = |dev_name: &str| registry.id(dev_name).unwrap_or_else(|| panic!("block definitions are missing \"{dev_name}\" which world generation needs"));
+
+        BiomeGenerator {
+            heightmap: config.heightmap,
+            biomes: config.biomes,
+            caves: config.caves,
+            ores: config.ores.iter().map(|ore| (required_block(&ore.block), ore.clone())).collect(),
+            blocks: Biome::ALL.map(|biome| {
+                let (surface, subsurface) = biome.block_names();
+                (required_block(surface), required_block(subsurface))
+            }),
+            stone_block: required_block("stone"),
+            subsurface_depth: 3,
+            features: [
+                Box::new(Tree {
+                    log: required_block("oak_log"),
+                    leaves: required_block("oak_leaves"),
+                    min_height: 4,
+                    max_height: 6
+                }),
+                Box::new(Flower {
+                    blocks: vec![required_block("dandelion"), required_block("poppy")]
+                }),
+                Box::new(Boulder {
+                    block: required_block("cobblestone"),
+                    min_radius: 1,
+                    max_radius: 2
+                })
+            ],
+            // logs go through leaves and anything solid crushes flowers
+            decoration_blocks: ["dandelion", "poppy", "oak_leaves", "oak_log", "cobblestone"].map(required_block).to_vec(),
+            surface_noise: OnceLock::new()
+        }
+    }
+
+    fn column(&self, noise: &HeightmapNoise, biome_map: &BiomeMap, x: i32, z: i32) -> (i32, Biome) {
+        let weights = biome_map.weights(x, z);
+        let sample = noise.sample(x, z);
+
+        let mut height = self.heightmap.base_height as f64;
+        for (biome, weight) in Biome::ALL.iter().zip(weights) {
+            let (offset, amplitude) = biome.height_profile();
+            // mountains only go up, folding the noise also gives them sharp ridges
+            let sample = if *biome == Biome::Mountains { sample.abs() } else { sample };
+
+            height += weight * (offset + sample * self.heightmap.amplitude * amplitude);
+        }
+
+        (height.floor() as i32, strongest(&weights))
+    }
+}
+
+impl TerrainGenerator for BiomeGenerator {
+    fn generate(&self, position: GlobalPos, seed: u32) -> Chunk {
+        let noise = HeightmapNoise::new(self.heightmap, seed);
+        let biome_map = BiomeMap::new(self.biomes, seed);
+        let cave_noise = CaveNoise::new(self.caves, seed);
+
+        let size = Chunk::SIZE as i32;
+        let columns = (0..size * size).map(|i| self.column(&noise, &biome_map, position.x + i % size, position.z + i / size)).collect::<Vec<_>>();
+
+        let mut chunk = Chunk::new(position, |global_pos| {
+            let (height, biome) = columns[((global_pos.x - position.x) + (global_pos.z - position.z) * size) as usize];
+            let (surface, subsurface) = self.blocks[biome as usize];
+            let depth = height - 1 - global_pos.y;
+
+            if depth < 0 || cave_noise.is_cave(global_pos, height) {
+                BlockId::AIR
+            } else if depth == 0 {
+                surface
+            } else if depth <= self.subsurface_depth {
+                subsurface
+            } else {
+                self.stone_block
+            }
+        });
+
+        caves::place_ores(&mut chunk, &self.ores, self.stone_block, seed);
+
+        chunk
+    }
+
+    fn biome_at(&self, position: GlobalPos, seed: u32) -> Option<Biome> {
+        Some(BiomeMap::new(self.biomes, seed).biome(position.x, position.z))
+    }
+
+    fn surface_height(&self, x: i32, z: i32, seed: u32) -> Option<i32> {
+        let (noise_seed, noise, biome_map) = self.surface_noise.get_or_init(|| (seed, HeightmapNoise::new(self.heightmap, seed), BiomeMap::new(self.biomes, seed)));
+        if *noise_seed != seed {
+            return Some(self.column(&HeightmapNoise::new(self.heightmap, seed), &BiomeMap::new(self.biomes, seed), x, z).0);
+        }
+
+        Some(self.column(noise, biome_map, x, z).0)
+    }
+
+    fn decorate(&self, chunk: &Chunk, seed: u32) -> Vec<(GlobalPos, BlockId)> {
+        let noise = HeightmapNoise::new(self.heightmap, seed);
+        let biome_map = BiomeMap::new(self.biomes, seed);
+
+        let size = Chunk::SIZE as i32;
+        let position = chunk.position();
+        let mut random = Random::at(seed, position, 0xDEC0);
+        let mut blocks = Vec::new();
+
+        for (i, feature) in self.features.iter().enumerate() {
+            // the most any biome wants, columns in other biomes get skipped below
+            let density = Biome::ALL.iter().map(|biome| biome.decoration_density()[i]).fold(0.0, f64::max);
+
+            let mut attempts = density.floor() as u32;
+            if random.chance(density.fract()) {
+                attempts += 1;
+            }
+
+            for _ in 0..attempts {
+                let (x, z) = (position.x + random.range(0, size), position.z + random.range(0, size));
+                let (height, biome) = self.column(&noise, &biome_map, x, z);
+
+                // the chunk the surface block is in owns the column, so every feature only gets placed once
+                let surface = glm::vec3(x, height - 1, z) - position;
+                let in_chunk = surface.y >= 0 && surface.y < size;
+
+                if !random.chance(biome.decoration_density()[i] / density) || !in_chunk || chunk.get_block(glm::vec3(surface.x as i8, surface.y as i8, surface.z as i8)) != Some(self.blocks[biome as usize].0) {
+                    continue;
+                }
+
+                feature.place(glm::vec3(x, height, z), &mut random, &mut blocks);
+            }
+        }
+
+        blocks
+    }
+
+    fn decoration_priority(&self, block: BlockId) -> Option<u32> {
+        self.decoration_blocks.iter().position(|b| *b == block).map(|i| i as u32 + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{config::WorldConfig, definitions, generation::TerrainGenerator};
+    use super::{Biome, BiomeGenerator, BiomeMap, BiomeSettings};
+
+    const COLUMNS: [(i32, i32); 5] = [(0, 0), (17, -5), (-300, 1200), (100000, -100000), (-1, -1)];
+
+    fn generator() -> BiomeGenerator {
+        BiomeGenerator::new(&WorldConfig::default(), &definitions::load_block_definitions("blocks.json").unwrap())
+    }
+
+    /// if these change then every existing world gets a seam where old chunks meet newly generated ones
+    #[test]
+    fn biomes_and_heights_dont_change() {
+        let generator = generator();
+        let columns = |seed| {
+            let biome_map = BiomeMap::new(BiomeSettings::default(), seed);
+            (COLUMNS.map(|(x, z)| biome_map.biome(x, z)), COLUMNS.map(|(x, z)| generator.surface_height(x, z, seed).unwrap()))
+        };
+
+        assert_eq!(columns(0), ([Biome::Plains, Biome::Plains, Biome::Tundra, Biome::Plains, Biome::Plains], [0, 7, -1, -3, -1]));
+        assert_eq!(columns(42), ([Biome::Plains, Biome::Plains, Biome::Desert, Biome::Forest, Biome::Plains], [0, 1, 9, 18, 0]));
+        assert_eq!(columns(12345), ([Biome::Plains, Biome::Plains, Biome::Tundra, Biome::Forest, Biome::Plains], [0, 3, -1, 15, 0]));
+    }
+
+    #[test]
+    fn heights_blend_across_borders() {
+        let generator = generator();
+        let biome_map = BiomeMap::new(BiomeSettings::default(), 42);
+
+        // goes in and out of two mountain ranges, which are 24 blocks higher than the rest
+        let columns = (-1200..1000).map(|x| (biome_map.biome(x, 0), generator.surface_height(x, 0, 42).unwrap())).collect::<Vec<_>>();
+        let borders = columns.windows(2).filter(|pair| pair[0].0 != pair[1].0).collect::<Vec<_>>();
+        assert!(borders.iter().filter(|pair| pair[0].0 == Biome::Mountains || pair[1].0 == Biome::Mountains).count() >= 4);
+
+        for pair in columns.windows(2) {
+            assert!((pair[0].1 - pair[1].1).abs() <= 3, "{:?} next to {:?}", pair[0], pair[1]);
+        }
+
+        for x in -1200..1000 {
+            let weights = biome_map.weights(x, 0);
+            assert!((weights.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            assert!(weights.iter().zip(biome_map.weights(x + 1, 0)).all(|(a, b)| (a - b).abs() < 0.05));
+        }
+    }
+}
@@ -0,0 +1,246 @@
+use noise::{NoiseFn, Perlin};
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}};
+use super::random::Random;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaveSettings {
+    /// big open caverns, carved wherever the noise is above `cheese_threshold`
+    pub cheese_frequency: f64,
+    pub cheese_threshold: f64,
+    /// long tunnels, carved where two noises are both close to 0
+    pub spaghetti_frequency: f64,
+    pub spaghetti_thickness: f64,
+    /// caves dont get closer to the surface than this so the ground doesnt end up full of holes
+    pub surface_margin: i32
+}
+
+impl Default for CaveSettings {
+    fn default() -> CaveSettings {
+        CaveSettings {
+            cheese_frequency: 1.0 / 48.0,
+            cheese_threshold: 0.55,
+            spaghetti_frequency: 1.0 / 64.0,
+            spaghetti_thickness: 0.05,
+            surface_margin: 6
+        }
+    }
+}
+
+/// one kind of ore, `block` is a dev name since its read from the world config
+#[derive(Debug, Clone, PartialEq)]
+pub struct OreSettings {
+    pub block: String,
+    /// average veins per chunk, 0.5 means every other chunk
+    pub veins_per_chunk: f64,
+    /// how many blocks one vein tries to place
+    pub vein_size: u32,
+    pub min_y: i32,
+    pub max_y: i32
+}
+
+impl OreSettings {
+    pub fn default_ores() -> Vec<OreSettings> {
+        vec![
+            OreSettings {
+                block: "coal_ore".to_string(),
+                veins_per_chunk: 6.0,
+                vein_size: 12,
+                min_y: -128,
+                max_y: 64
+            },
+            OreSettings {
+                block: "iron_ore".to_string(),
+                veins_per_chunk: 3.0,
+                vein_size: 8,
+                min_y: -160,
+                max_y: 0
+            },
+            OreSettings {
+                block: "gold_ore".to_string(),
+                veins_per_chunk: 1.0,
+                vein_size: 6,
+                min_y: -256,
+                max_y: -48
+            },
+            OreSettings {
+                block: "diamond_ore".to_string(),
+                veins_per_chunk: 0.5,
+                vein_size: 4,
+                min_y: -512,
+                max_y: -96
+            }
+        ]
+    }
+}
+
+/// 3d noise for one seed that says which underground blocks get hollowed out
+pub struct CaveNoise {
+    settings: CaveSettings,
+    cheese: Perlin,
+    spaghetti_a: Perlin,
+    spaghetti_b: Perlin
+}
+
+impl CaveNoise {
+    pub fn new(settings: CaveSettings, seed: u32) -> CaveNoise {
+        CaveNoise {
+            settings,
+            cheese: Perlin::new(seed.wrapping_add(200)),
+            spaghetti_a: Perlin::new(seed.wrapping_add(201)),
+            spaghetti_b: Perlin::new(seed.wrapping_add(202))
+        }
+    }
+
+    /// `surface_height` is the height of the column `position` is in
+    pub fn is_cave(&self, position: GlobalPos, surface_height: i32) -> bool {
+        if position.y >= surface_height - self.settings.surface_margin {
+            return false;
+        }
+
+        let pos = [position.x as f64, position.y as f64, position.z as f64];
+        let scaled = |frequency: f64| [pos[0] * frequency, pos[1] * frequency, pos[2] * frequency];
+
+        if self.cheese.get(scaled(self.settings.cheese_frequency)) > self.settings.cheese_threshold {
+            return true;
+        }
+
+        // the second noise is squashed vertically so tunnels are mostly horizontal
+        let spaghetti = scaled(self.settings.spaghetti_frequency);
+        self.spaghetti_a.get(spaghetti).abs() < self.settings.spaghetti_thickness &&
+        self.spaghetti_b.get([spaghetti[0], spaghetti[1] * 2.0, spaghetti[2]]).abs() < self.settings.spaghetti_thickness
+    }
+}
+
+/// scatters ore veins into `chunk`, only ever replacing `replaceable`
+///
+/// veins stay inside of the chunk and only depend on the seed and chunk position
+pub fn place_ores(chunk: &mut Chunk, ores: &[(BlockId, OreSettings)], replaceable: BlockId, seed: u32) {
+    let size = Chunk::SIZE as i32;
+
+    for (i, (ore, settings)) in ores.iter().enumerate() {
+        let min_y = settings.min_y.max(chunk.position().y);
+        let max_y = settings.max_y.min(chunk.position().y + size);
+        if min_y >= max_y {
+            continue;
+        }
+
+        let mut random = Random::at(seed, chunk.position(), 0x0BE5 + i as u64);
+
+        let mut veins = settings.veins_per_chunk.floor() as u32;
+        if random.chance(settings.veins_per_chunk.fract()) {
+            veins += 1;
+        }
+
+        for _ in 0..veins {
+            let mut pos = glm::vec3(random.range(0, size), random.range(min_y, max_y) - chunk.position().y, random.range(0, size));
+
+            // random walk, so veins come out as little blobs
+            for _ in 0..settings.vein_size {
+                let local_pos = glm::vec3(pos.x as i8, pos.y as i8, pos.z as i8);
+                if chunk.get_block(local_pos) == Some(replaceable) {
+                    chunk.set_block(local_pos, *ore);
+                }
+
+                match random.range(0, 3) {
+                    0 => pos.x = (pos.x + random.range(-1, 2)).clamp(0, size - 1),
+                    1 => pos.y = (pos.y + random.range(-1, 2)).clamp(0, size - 1),
+                    _ => pos.z = (pos.z + random.range(-1, 2)).clamp(0, size - 1)
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}};
+    use super::{place_ores, CaveNoise, CaveSettings, OreSettings};
+
+    const STONE: BlockId = BlockId(1);
+
+    /// cave blocks in the 40 block cube with its corner at `corner`
+    fn cave_count(seed: u32, corner: GlobalPos) -> usize {
+        let noise = CaveNoise::new(CaveSettings::default(), seed);
+        (0..40 * 40 * 40).filter(|i| noise.is_cave(corner + glm::vec3(i % 40, i / 40 % 40, i / 1600), 0)).count()
+    }
+
+    fn ores(seed: u32, position: GlobalPos) -> Chunk {
+        let ores = OreSettings::default_ores().into_iter().enumerate().map(|(i, ore)| (BlockId(i as u16 + 2), ore)).collect::<Vec<_>>();
+        // the top half is air, ores cant go there
+        let mut chunk = Chunk::new(position, |pos| if pos.y - position.y < 10 { STONE } else { BlockId::AIR });
+        place_ores(&mut chunk, &ores, STONE, seed);
+
+        chunk
+    }
+
+    fn ore_counts(chunk: &Chunk) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for i in 0..Chunk::VOLUME {
+            let block = chunk.get_block(Chunk::local_pos(i)).unwrap();
+            if block.0 >= 2 {
+                counts[block.0 as usize - 2] += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// if these change then every existing world gets a seam where old chunks meet newly generated ones
+    #[test]
+    fn caves_dont_change() {
+        assert_eq!(cave_count(0, glm::vec3(0, -60, 0)), 5368);
+        assert_eq!(cave_count(1, glm::vec3(0, -60, 0)), 1674);
+        assert_eq!(cave_count(42, glm::vec3(-100, -200, 300)), 821);
+        assert_eq!(cave_count(12345, glm::vec3(1000, -45, -1000)), 9767);
+    }
+
+    #[test]
+    fn caves_stay_below_the_surface_margin() {
+        let settings = CaveSettings::default();
+        let noise = CaveNoise::new(settings, 0);
+        let corner = glm::vec3(0, -60, 0);
+
+        // the same cube as in `caves_dont_change`, with the surface moved down into it
+        for surface_height in [-40, -20, 0] {
+            let caves = (0..40 * 40 * 40).map(|i| corner + glm::vec3(i % 40, i / 40 % 40, i / 1600)).filter(|pos| noise.is_cave(*pos, surface_height)).collect::<Vec<_>>();
+
+            assert!(!caves.is_empty());
+            assert!(caves.iter().all(|pos| pos.y < surface_height - settings.surface_margin));
+        }
+    }
+
+    #[test]
+    fn ores_dont_change() {
+        assert_eq!(ore_counts(&ores(0, glm::vec3(0, -120, 0))), [11, 3, 4, 0]);
+        assert_eq!(ore_counts(&ores(42, glm::vec3(0, -120, 0))), [20, 15, 0, 3]);
+        assert_eq!(ore_counts(&ores(42, glm::vec3(20, -140, -40))), [0, 4, 3, 3]);
+        assert_eq!(ore_counts(&ores(7, glm::vec3(20, -140, -40))), [3, 8, 3, 0]);
+
+        let (a, b) = (ores(42, glm::vec3(0, -120, 0)), ores(42, glm::vec3(0, -120, 0)));
+        assert!((0..Chunk::VOLUME).all(|i| a.get_block(Chunk::local_pos(i)) == b.get_block(Chunk::local_pos(i))));
+    }
+
+    #[test]
+    fn ores_only_replace_stone_in_their_range() {
+        let settings = OreSettings::default_ores();
+
+        for position in [glm::vec3(0, -120, 0), glm::vec3(20, -140, -40), glm::vec3(-20, 40, 0)] {
+            let chunk = ores(42, position);
+
+            for i in 0..Chunk::VOLUME {
+                let local_pos = Chunk::local_pos(i);
+                let block = chunk.get_block(local_pos).unwrap();
+                if block.0 < 2 {
+                    continue;
+                }
+
+                let ore = &settings[block.0 as usize - 2];
+                let y = position.y + local_pos.y as i32;
+                assert!(local_pos.y < 10 && y >= ore.min_y && y < ore.max_y, "{} at {y}", ore.block);
+            }
+        }
+
+        // coal goes up to 64
+        assert_eq!(ore_counts(&ores(42, glm::vec3(-20, 80, 0))), [0; 4]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos, LocalPos}, ChunkPos};
+use super::random::Random;
+
+/// something bigger than a block that gets placed on top of the terrain, like a tree
+pub trait Feature: Send + Sync {
+    /// pushes the blocks of one feature standing on top of `base` into `blocks`
+    ///
+    /// blocks are allowed to go outside of the chunk `base` is in
+    fn place(&self, base: GlobalPos, random: &mut Random, blocks: &mut Vec<(GlobalPos, BlockId)>);
+}
+
+pub struct Tree {
+    pub log: BlockId,
+    pub leaves: BlockId,
+    pub min_height: i32,
+    pub max_height: i32
+}
+
+impl Feature for Tree {
+    fn place(&self, base: GlobalPos, random: &mut Random, blocks: &mut Vec<(GlobalPos, BlockId)>) {
+        let height = random.range(self.min_height, self.max_height + 1);
+
+        for y in 0..height {
+            blocks.push((base + glm::vec3(0, y, 0), self.log));
+        }
+
+        // 2 wide layers around the top of the trunk and a smaller cross on top
+        for y in height - 2..=height {
+            let radius: i32 = if y == height { 1 } else { 2 };
+
+            for x in -radius..=radius {
+                for z in -radius..=radius {
+                    let corner = x.abs() == radius && z.abs() == radius;
+                    // random corners so every tree doesnt look the same
+                    if (x == 0 && z == 0 && y < height) || (corner && (radius == 1 || random.chance(0.5))) {
+                        continue;
+                    }
+
+                    blocks.push((base + glm::vec3(x, y, z), self.leaves));
+                }
+            }
+        }
+    }
+}
+
+pub struct Boulder {
+    pub block: BlockId,
+    pub min_radius: i32,
+    pub max_radius: i32
+}
+
+impl Feature for Boulder {
+    fn place(&self, base: GlobalPos, random: &mut Random, blocks: &mut Vec<(GlobalPos, BlockId)>) {
+        let radius = random.range(self.min_radius, self.max_radius + 1);
+        // a bit more than the radius so the edges arent too blocky
+        let radius_squared = radius * radius + radius;
+
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                for z in -radius..=radius {
+                    if x * x + y * y + z * z <= radius_squared {
+                        blocks.push((base + glm::vec3(x, y, z), self.block));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// one random block out of `blocks`
+pub struct Flower {
+    pub blocks: Vec<BlockId>
+}
+
+impl Feature for Flower {
+    fn place(&self, base: GlobalPos, random: &mut Random, blocks: &mut Vec<(GlobalPos, BlockId)>) {
+        if !self.blocks.is_empty() {
+            blocks.push((base, self.blocks[random.range(0, self.blocks.len() as i32) as usize]));
+        }
+    }
+}
+
+/// decides if a decoration block can go where `current` is
+///
+/// `priority` has to be a strict order (see `TerrainGenerator::decoration_priority`),
+/// that way the higher one always wins no matter which feature got placed first
+pub fn can_replace(current: BlockId, new: BlockId, priority: impl Fn(BlockId) -> Option<u32>) -> bool {
+    if current == BlockId::AIR {
+        return new != BlockId::AIR;
+    }
+
+    match (priority(current), priority(new)) {
+        (Some(current), Some(new)) => new > current,
+        _ => false
+    }
+}
+
+/// decoration blocks that landed in chunks that arent loaded, theyre placed once the chunk is loaded
+///
+/// a chunk that got blocks from its neighbours features has to be saved after that, generating it
+/// again wouldnt bring them back. that way nothing stays in here once its chunk was loaded
+#[derive(Debug, Clone, Default)]
+pub struct PendingBlocks {
+    chunks: HashMap<GlobalPos, HashMap<LocalPos, BlockId>>
+}
+
+impl PendingBlocks {
+    pub fn new() -> PendingBlocks {
+        PendingBlocks::default()
+    }
+
+    /// `chunk_position` is the position of the chunk that `local_pos` is in
+    pub fn push(&mut self, chunk_position: GlobalPos, local_pos: LocalPos, block: BlockId, priority: impl Fn(BlockId) -> Option<u32>) {
+        let current = self.chunks.entry(chunk_position).or_default().entry(local_pos).or_insert(BlockId::AIR);
+
+        if can_replace(*current, block, priority) {
+            *current = block;
+        }
+    }
+
+    /// places everything queued for this chunk into it and forgets it, false if there wasnt anything
+    pub fn apply(&mut self, chunk: &mut Chunk, priority: impl Fn(BlockId) -> Option<u32>) -> bool {
+        let Some(blocks) = self.chunks.remove(&chunk.position()) else {
+            return false;
+        };
+
+        for (local_pos, block) in blocks {
+            if can_replace(chunk.get_block(local_pos).unwrap(), block, &priority) {
+                chunk.set_block(local_pos, block);
+            }
+        }
+
+        true
+    }
+}
+
+/// puts the blocks of the features that start in the chunk at `key` into the loaded chunks,
+/// the ones for chunks that arent loaded get queued in `pending`
+///
+/// gives back the positions of the blocks that were placed in other chunks
+pub fn place(chunks: &mut HashMap<ChunkPos, Chunk>, pending: &mut PendingBlocks, key: ChunkPos, blocks: Vec<(GlobalPos, BlockId)>, priority: impl Fn(BlockId) -> Option<u32>) -> Vec<GlobalPos> {
+    let mut placed_elsewhere = Vec::new();
+
+    for (global_pos, block) in blocks {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        let target_key = chunk_position / Chunk::SIZE as i32;
+
+        let Some(target) = chunks.get_mut(&target_key) else {
+            pending.push(chunk_position, local_pos, block, &priority);
+            continue;
+        };
+
+        if can_replace(target.get_block(local_pos).unwrap(), block, &priority) {
+            target.set_block(local_pos, block);
+
+            if target_key != key {
+                placed_elsewhere.push(global_pos);
+            }
+        }
+    }
+
+    placed_elsewhere
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}, generation::random::Random, ChunkPos};
+    use super::{place, Boulder, Feature, PendingBlocks, Tree};
+
+    const LEAVES: BlockId = BlockId(1);
+    const LOG: BlockId = BlockId(2);
+    const STONE: BlockId = BlockId(3);
+
+    fn priority(block: BlockId) -> Option<u32> {
+        [LEAVES, LOG, STONE].iter().position(|b| *b == block).map(|i| i as u32)
+    }
+
+    /// a tree near the border of (0, 0, 0) and a boulder in (1, 0, 0) that both grow into the other chunk
+    fn features(key: ChunkPos) -> Vec<(GlobalPos, BlockId)> {
+        let mut blocks = Vec::new();
+        let mut random = Random::at(0, key, 0);
+
+        if key == glm::vec3(0, 0, 0) {
+            Tree { log: LOG, leaves: LEAVES, min_height: 5, max_height: 5 }.place(glm::vec3(19, 1, 10), &mut random, &mut blocks);
+        } else {
+            Boulder { block: STONE, min_radius: 2, max_radius: 2 }.place(glm::vec3(21, 5, 10), &mut random, &mut blocks);
+        }
+
+        blocks
+    }
+
+    /// what `World::decorate_chunk` does when a chunk comes in
+    fn load(chunks: &mut HashMap<ChunkPos, Chunk>, pending: &mut PendingBlocks, key: ChunkPos, chunk: Chunk) {
+        chunks.insert(key, chunk);
+        place(chunks, pending, key, features(key), priority);
+        pending.apply(chunks.get_mut(&key).unwrap(), priority);
+    }
+
+    fn blocks(chunks: &HashMap<ChunkPos, Chunk>) -> Vec<Vec<BlockId>> {
+        [glm::vec3(0, 0, 0), glm::vec3(1, 0, 0)].iter().map(|key| (0..Chunk::VOLUME).map(|i| chunks[key].get_block(Chunk::local_pos(i)).unwrap()).collect()).collect()
+    }
+
+    #[test]
+    fn pending_blocks_are_applied_once() {
+        let chunk_position = glm::vec3(20, 0, -20);
+
+        let mut pending = PendingBlocks::new();
+        pending.push(chunk_position, glm::vec3(1, 2, 3), LEAVES, priority);
+        pending.push(chunk_position, glm::vec3(1, 2, 3), LOG, priority);
+        pending.push(chunk_position, glm::vec3(1, 2, 3), LEAVES, priority);
+        pending.push(glm::vec3(0, 0, 0), glm::vec3(1, 2, 3), STONE, priority);
+
+        let mut chunk = Chunk::new(chunk_position, |_| BlockId::AIR);
+        assert!(pending.apply(&mut chunk, priority));
+        assert_eq!(chunk.get_block(glm::vec3(1, 2, 3)), Some(LOG));
+        assert_eq!(chunk.blocks().palette().len(), 2);
+
+        // the chunk has to be saved now, so theres nothing left for it
+        let mut chunk = Chunk::new(chunk_position, |_| BlockId::AIR);
+        assert!(!pending.apply(&mut chunk, priority));
+        assert_eq!(chunk.get_block(glm::vec3(1, 2, 3)), Some(BlockId::AIR));
+
+        let mut other = Chunk::new(glm::vec3(0, 0, 0), |_| BlockId::AIR);
+        assert!(pending.apply(&mut other, priority));
+        assert_eq!(other.get_block(glm::vec3(1, 2, 3)), Some(STONE));
+    }
+
+    #[test]
+    fn load_order_doesnt_matter() {
+        let (a, b) = (glm::vec3(0, 0, 0), glm::vec3(1, 0, 0));
+        let empty = |key: ChunkPos| Chunk::new(key * Chunk::SIZE as i32, |_| BlockId::AIR);
+
+        let mut a_then_b = (HashMap::new(), PendingBlocks::new());
+        load(&mut a_then_b.0, &mut a_then_b.1, a, empty(a));
+        load(&mut a_then_b.0, &mut a_then_b.1, b, empty(b));
+
+        let mut b_then_a = (HashMap::new(), PendingBlocks::new());
+        load(&mut b_then_a.0, &mut b_then_a.1, b, empty(b));
+        load(&mut b_then_a.0, &mut b_then_a.1, a, empty(a));
+
+        // a gets saved and unloaded before b is generated, then comes back from disk
+        // (without its features, those are already in the saved chunk)
+        let mut a_saved_first = (HashMap::new(), PendingBlocks::new());
+        load(&mut a_saved_first.0, &mut a_saved_first.1, a, empty(a));
+        let saved = a_saved_first.0.remove(&a).unwrap();
+        load(&mut a_saved_first.0, &mut a_saved_first.1, b, empty(b));
+        a_saved_first.0.insert(a, saved);
+        a_saved_first.1.apply(a_saved_first.0.get_mut(&a).unwrap(), priority);
+
+        // the boulder crushes the tree on both sides of the border
+        assert_eq!(a_then_b.0[&a].get_block(glm::vec3(19, 5, 10)), Some(STONE));
+        assert_eq!(a_then_b.0[&a].get_block(glm::vec3(18, 5, 10)), Some(LEAVES));
+        assert_eq!(a_then_b.0[&b].get_block(glm::vec3(0, 6, 10)), Some(STONE));
+
+        assert_eq!(blocks(&a_then_b.0), blocks(&b_then_a.0));
+        assert_eq!(blocks(&a_then_b.0), blocks(&a_saved_first.0));
+        assert!(a_then_b.1.chunks.is_empty() && b_then_a.1.chunks.is_empty() && a_saved_first.1.chunks.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use std::sync::OnceLock;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}};
+use super::TerrainGenerator;
+
+/// everything that shapes the terrain, comes from `WorldConfig`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightmapSettings {
+    pub octaves: u32,
+    /// frequency of the first octave in cycles per block
+    pub frequency: f64,
+    /// how much the frequency goes up every octave
+    pub lacunarity: f64,
+    /// how much the amplitude goes down every octave
+    pub persistence: f64,
+    /// average surface height
+    pub base_height: i32,
+    /// how far the surface goes above and below `base_height`
+    pub amplitude: f64,
+    /// how many blocks the sample position gets pushed around by, 0 turns warping off
+    pub warp_strength: f64,
+    pub warp_frequency: f64
+}
+
+impl Default for HeightmapSettings {
+    fn default() -> HeightmapSettings {
+        HeightmapSettings {
+            octaves: 5,
+            frequency: 1.0 / 256.0,
+            lacunarity: 2.0,
+            persistence: 0.5,
+            base_height: 0,
+            amplitude: 32.0,
+            warp_strength: 24.0,
+            warp_frequency: 1.0 / 128.0
+        }
+    }
+}
+
+/// the noise functions for one seed, building them is too expensive to do for every column
+pub struct HeightmapNoise {
+    settings: HeightmapSettings,
+    fbm: Fbm<Perlin>,
+    warp_x: Perlin,
+    warp_z: Perlin
+}
+
+impl HeightmapNoise {
+    pub fn new(settings: HeightmapSettings, seed: u32) -> HeightmapNoise {
+        HeightmapNoise {
+            settings,
+            fbm: Fbm::<Perlin>::new(seed)
+                .set_octaves(settings.octaves.clamp(1, Fbm::<Perlin>::MAX_OCTAVES as u32) as usize)
+                .set_frequency(settings.frequency)
+                .set_lacunarity(settings.lacunarity)
+                .set_persistence(settings.persistence),
+            warp_x: Perlin::new(seed.wrapping_add(1)),
+            warp_z: Perlin::new(seed.wrapping_add(2))
+        }
+    }
+
+    /// surface height of the column, everything below it is ground
+    pub fn height(&self, x: i32, z: i32) -> i32 {
+        self.settings.base_height + (self.sample(x, z) * self.settings.amplitude).floor() as i32
+    }
+
+    /// fractal noise at the column in roughly -1..1
+    pub fn sample(&self, x: i32, z: i32) -> f64 {
+        let (mut x, mut z) = (x as f64, z as f64);
+
+        if self.settings.warp_strength != 0.0 {
+            let warp_pos = [x * self.settings.warp_frequency, z * self.settings.warp_frequency];
+            x += self.warp_x.get(warp_pos) * self.settings.warp_strength;
+            z += self.warp_z.get(warp_pos) * self.settings.warp_strength;
+        }
+
+        self.fbm.get([x, z])
+    }
+}
+
+/// multi octave fractal heightmap with optional domain warping
+///
+/// the surface is `surface_block`, then `filler_depth` blocks of `filler_block` and then `stone_block` all the way down
+pub struct HeightmapGenerator {
+    settings: HeightmapSettings,
+    surface_block: BlockId,
+    filler_block: BlockId,
+    stone_block: BlockId,
+    filler_depth: i32,
+    /// noise for the first seed `surface_height` got, the lighting asks for one column at a time
+    surface_noise: OnceLock<(u32, HeightmapNoise)>
+}
+
+impl HeightmapGenerator {
+    pub fn new(settings: HeightmapSettings, surface_block: BlockId, filler_block: BlockId, stone_block: BlockId) -> HeightmapGenerator {
+        HeightmapGenerator {
+            settings,
+            surface_block,
+            filler_block,
+            stone_block,
+            filler_depth: 3,
+            surface_noise: OnceLock::new()
+        }
+    }
+}
+
+impl TerrainGenerator for HeightmapGenerator {
+    fn generate(&self, position: GlobalPos, seed: u32) -> Chunk {
+        let noise = HeightmapNoise::new(self.settings, seed);
+
+        // only 1 sample per column instead of 1 per block
+        let size = Chunk::SIZE as i32;
+        let heights = (0..size * size).map(|i| noise.height(position.x + i % size, position.z + i / size)).collect::<Vec<_>>();
+
+        Chunk::new(position, |global_pos| {
+            let height = heights[((global_pos.x - position.x) + (global_pos.z - position.z) * size) as usize];
+            let depth = height - 1 - global_pos.y;
+
+            if depth < 0 {
+                BlockId::AIR
+            } else if depth == 0 {
+                self.surface_block
+            } else if depth <= self.filler_depth {
+                self.filler_block
+            } else {
+                self.stone_block
+            }
+        })
+    }
+
+    fn surface_height(&self, x: i32, z: i32, seed: u32) -> Option<i32> {
+        let (noise_seed, noise) = self.surface_noise.get_or_init(|| (seed, HeightmapNoise::new(self.settings, seed)));
+        if *noise_seed != seed {
+            return Some(HeightmapNoise::new(self.settings, seed).height(x, z));
+        }
+
+        Some(noise.height(x, z))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{block::BlockId, generation::TerrainGenerator};
+    use super::{HeightmapGenerator, HeightmapNoise, HeightmapSettings};
+
+    const COLUMNS: [(i32, i32); 5] = [(0, 0), (17, -5), (-300, 1200), (100000, -100000), (-1, -1)];
+
+    fn heights(seed: u32) -> [i32; 5] {
+        let noise = HeightmapNoise::new(HeightmapSettings::default(), seed);
+        COLUMNS.map(|(x, z)| noise.height(x, z))
+    }
+
+    /// if these change then every existing world gets a seam where old chunks meet newly generated ones
+    #[test]
+    fn heights_dont_change() {
+        assert_eq!(heights(0), [0, 12, -9, -8, -2]);
+        assert_eq!(heights(1), [0, 9, -7, -8, -1]);
+        assert_eq!(heights(42), [0, 1, 26, 20, -1]);
+        assert_eq!(heights(12345), [0, 3, -8, 22, -1]);
+    }
+
+    #[test]
+    fn seeds_give_different_heights() {
+        let columns = (0..64).map(|i| (i * 37, i * -53)).collect::<Vec<_>>();
+        let heights = |seed| {
+            let noise = HeightmapNoise::new(HeightmapSettings::default(), seed);
+            columns.iter().map(|(x, z)| noise.height(*x, *z)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(heights(7), heights(7));
+        assert_ne!(heights(7), heights(8));
+        assert_ne!(heights(0), heights(1_000_000));
+    }
+
+    #[test]
+    fn surface_height_with_any_seed() {
+        let generator = HeightmapGenerator::new(HeightmapSettings::default(), BlockId(1), BlockId(2), BlockId(3));
+
+        // the first seed gets cached, the others still have to come out right
+        for seed in [42, 0, 42, 12345] {
+            assert_eq!(COLUMNS.map(|(x, z)| generator.surface_height(x, z, seed).unwrap()), heights(seed));
+        }
+    }
+}
@@ -0,0 +1,52 @@
+pub mod biome;
+pub mod caves;
+pub mod decoration;
+pub mod heightmap;
+pub mod random;
+pub mod superflat;
+pub mod void;
+
+use super::{block::BlockId, chunk::{Chunk, GlobalPos}};
+use self::biome::Biome;
+
+/// decides what a chunk looks like when its first generated
+///
+/// has to give back the exact same chunk for the same position and seed
+/// since chunks get thrown away and regenerated all the time
+///
+/// chunks get generated on worker threads so it has to be `Send` and `Sync`
+pub trait TerrainGenerator: Send + Sync {
+    /// `position` is the global position of the chunks corner (the same as `Chunk::position`)
+    fn generate(&self, position: GlobalPos, seed: u32) -> Chunk;
+
+    /// None for generators that dont have biomes
+    fn biome_at(&self, _position: GlobalPos, _seed: u32) -> Option<Biome> {
+        None
+    }
+
+    /// y of the first air block above the terrain in the column, None if the generator cant tell
+    fn surface_height(&self, _x: i32, _z: i32, _seed: u32) -> Option<i32> {
+        None
+    }
+
+    /// blocks of the features (trees, boulders...) that start in a freshly generated chunk
+    ///
+    /// only allowed to look at `chunk` itself so the result doesnt depend on what else is loaded,
+    /// the blocks can go into other chunks
+    fn decorate(&self, _chunk: &Chunk, _seed: u32) -> Vec<(GlobalPos, BlockId)> {
+        Vec::new()
+    }
+
+    /// None for blocks that decorations cant replace (the terrain),
+    /// every block a decoration uses needs a different value so overlapping features always come out the same
+    fn decoration_priority(&self, _block: BlockId) -> Option<u32> {
+        None
+    }
+}
+
+/// any `Fn(GlobalPos, seed) -> BlockId` is a generator, mostly for quick experiments
+impl<F: Fn(GlobalPos, u32) -> BlockId + Send + Sync> TerrainGenerator for F {
+    fn generate(&self, position: GlobalPos, seed: u32) -> Chunk {
+        Chunk::new(position, |global_pos| self(global_pos, seed))
+    }
+}
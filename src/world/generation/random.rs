@@ -0,0 +1,56 @@
+use crate::world::chunk::GlobalPos;
+
+/// splitmix64, tiny and gives the same numbers on every platform which is all world gen needs
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: u64
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            state: seed
+        }
+    }
+
+    /// a generator that only depends on the seed, the position and `salt`
+    ///
+    /// use a different salt for every feature so they dont all roll the same numbers
+    pub fn at(seed: u32, position: GlobalPos, salt: u64) -> Random {
+        let mut random = Random::new(seed as u64 ^ salt.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        for coord in [position.x, position.y, position.z] {
+            random.state ^= coord as u32 as u64;
+            random.state = random.next_u64();
+        }
+
+        random
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// 0.0..1.0
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `min..max`, returns `min` if the range is empty
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        min + (self.next_u64() % (max - min) as u64) as i32
+    }
+
+    /// true `chance` of the time
+    pub fn chance(&mut self, chance: f64) -> bool {
+        self.next_f64() < chance
+    }
+}
@@ -0,0 +1,42 @@
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}};
+use super::TerrainGenerator;
+
+/// same layers everywhere, ignores the seed
+pub struct SuperflatGenerator {
+    /// (block, thickness) from the top down, the top of the first layer is at y = 0
+    layers: Vec<(BlockId, u32)>
+}
+
+impl SuperflatGenerator {
+    pub fn new(layers: Vec<(BlockId, u32)>) -> SuperflatGenerator {
+        SuperflatGenerator {
+            layers
+        }
+    }
+
+    fn block_at(&self, y: i32) -> BlockId {
+        if y >= 0 {
+            return BlockId::AIR;
+        }
+
+        let mut depth = (-y - 1) as u32;
+        for (block, thickness) in &self.layers {
+            if depth < *thickness {
+                return *block;
+            }
+            depth -= thickness;
+        }
+
+        BlockId::AIR
+    }
+}
+
+impl TerrainGenerator for SuperflatGenerator {
+    fn generate(&self, position: GlobalPos, _seed: u32) -> Chunk {
+        Chunk::new(position, |global_pos| self.block_at(global_pos.y))
+    }
+
+    fn surface_height(&self, _x: i32, _z: i32, _seed: u32) -> Option<i32> {
+        Some(0)
+    }
+}
@@ -0,0 +1,38 @@
+use crate::world::{block::BlockId, chunk::{Chunk, GlobalPos}};
+use super::TerrainGenerator;
+
+/// nothing but air
+pub struct VoidGenerator;
+
+impl TerrainGenerator for VoidGenerator {
+    fn generate(&self, position: GlobalPos, _seed: u32) -> Chunk {
+        Chunk::new(position, |_| BlockId::AIR)
+    }
+}
+
+/// a void world with every given block placed in a row along +x at y = 0, with a gap between each one
+///
+/// handy for checking textures and meshing of new blocks
+pub struct DebugGenerator {
+    blocks: Vec<BlockId>
+}
+
+impl DebugGenerator {
+    pub fn new(blocks: Vec<BlockId>) -> DebugGenerator {
+        DebugGenerator {
+            blocks
+        }
+    }
+}
+
+impl TerrainGenerator for DebugGenerator {
+    fn generate(&self, position: GlobalPos, _seed: u32) -> Chunk {
+        Chunk::new(position, |global_pos| {
+            if global_pos.y != 0 || global_pos.z != 0 || global_pos.x < 0 || global_pos.x % 2 != 0 {
+                return BlockId::AIR;
+            }
+
+            self.blocks.get(global_pos.x as usize / 2).copied().unwrap_or(BlockId::AIR)
+        })
+    }
+}
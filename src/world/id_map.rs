@@ -0,0 +1,108 @@
+use super::{block::BlockId, registry::BlockRegistry};
+
+/// the block ids chunks are saved with, they never change once a block has one
+/// so reordering blocks.json doesnt scramble old chunks
+///
+/// blocks that arent in the registry anymore keep their id but load as air
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockIdMap {
+    /// dev name of every saved id
+    names: Vec<String>,
+    /// saved id -> registry id
+    to_registry: Vec<BlockId>,
+    /// registry id -> saved id
+    to_saved: Vec<u16>
+}
+
+impl BlockIdMap {
+    /// `names` is what the world was saved with, new blocks in the registry get added to the end
+    pub fn new(mut names: Vec<String>, registry: &BlockRegistry) -> BlockIdMap {
+        for (_, block) in registry.iter() {
+            if !names.contains(&block.dev_name) {
+                names.push(block.dev_name.clone());
+            }
+        }
+
+        let to_registry = names.iter().map(|name| {
+            registry.id(name).unwrap_or_else(|| {
+                println!("Block \"{name}\" doesn't exist anymore, loading it as air");
+                BlockId::AIR
+            })
+        }).collect::<Vec<_>>();
+
+        let to_saved = registry.iter().map(|(_, block)| names.iter().position(|name| *name == block.dev_name).unwrap() as u16).collect();
+
+        BlockIdMap {
+            names,
+            to_registry,
+            to_saved
+        }
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// air for ids that werent ever saved
+    pub fn to_registry(&self, saved_id: u16) -> BlockId {
+        self.to_registry.get(saved_id as usize).copied().unwrap_or(BlockId::AIR)
+    }
+
+    pub fn to_saved(&self, block: BlockId) -> u16 {
+        self.to_saved[block.0 as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json::JsonValue, world::{block::{Block, BlockId, BlockType}, level::LevelData, registry::BlockRegistry}};
+    use super::BlockIdMap;
+
+    /// saved as air, stone, ruby_ore, dirt, grass_block and ruby_ore was removed since
+    const LEVEL_REORDERED: &str = include_str!("../../tests/fixtures/level_v2_reordered.json");
+
+    fn registry(dev_names: &[&str]) -> BlockRegistry {
+        let mut registry = BlockRegistry::new();
+        for dev_name in dev_names {
+            registry.register(Block::new(dev_name, dev_name, BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0)));
+        }
+
+        registry
+    }
+
+    #[test]
+    fn remaps_by_dev_name() {
+        let level = LevelData::from_json(&JsonValue::parse(LEVEL_REORDERED).unwrap()).unwrap();
+        let registry = registry(&["grass_block", "dirt", "stone", "sand"]);
+        let block_ids = BlockIdMap::new(level.block_ids, &registry);
+
+        // new blocks get the next free ids
+        assert_eq!(block_ids.names(), ["air", "stone", "ruby_ore", "dirt", "grass_block", "sand"]);
+
+        assert_eq!(block_ids.to_registry(0), BlockId::AIR);
+        assert_eq!(block_ids.to_registry(1), registry.id("stone").unwrap());
+        assert_eq!(block_ids.to_registry(3), registry.id("dirt").unwrap());
+        assert_eq!(block_ids.to_registry(4), registry.id("grass_block").unwrap());
+        assert_eq!(block_ids.to_registry(5), registry.id("sand").unwrap());
+        assert_eq!(block_ids.to_saved(registry.id("grass_block").unwrap()), 4);
+        assert_eq!(block_ids.to_saved(registry.id("sand").unwrap()), 5);
+
+        for (id, _) in registry.iter() {
+            assert_eq!(block_ids.to_registry(block_ids.to_saved(id)), id);
+        }
+    }
+
+    #[test]
+    fn removed_blocks_load_as_air() {
+        let level = LevelData::from_json(&JsonValue::parse(LEVEL_REORDERED).unwrap()).unwrap();
+        let block_ids = BlockIdMap::new(level.block_ids, &registry(&["stone", "dirt", "grass_block"]));
+
+        assert_eq!(block_ids.to_registry(2), BlockId::AIR);
+        // never saved at all
+        assert_eq!(block_ids.to_registry(100), BlockId::AIR);
+        // the id stays taken so a block with that name coming back gets its old blocks back
+        assert_eq!(block_ids.names()[2], "ruby_ore");
+        let registry = registry(&["ruby_ore"]);
+        assert_eq!(BlockIdMap::new(block_ids.names().to_vec(), &registry).to_registry(2), registry.id("ruby_ore").unwrap());
+    }
+}
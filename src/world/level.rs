@@ -0,0 +1,132 @@
+use std::{fmt::{self, Display}, path::Path};
+use crate::json::{JsonValue, ParseError};
+use super::{config::{ConfigError, WorldConfig}, migration, save::{self, SaveSettings}};
+
+/// everything about a world that isnt chunks, stored as `level.json` next to the region files
+///
+/// positions and directions are in camera space (like `Camera::position`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct LevelData {
+    /// seed is in here too
+    pub config: WorldConfig,
+    pub spawn: glm::Vec3,
+    /// seconds the world has been played for
+    pub game_time: f64,
+    pub player_position: glm::Vec3,
+    pub player_direction: glm::Vec3,
+    /// dev names of the block ids chunks are saved with (see `BlockIdMap`)
+    pub block_ids: Vec<String>,
+    pub save_settings: SaveSettings
+}
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(std::io::Error),
+    Json(ParseError),
+    Config(ConfigError),
+    Invalid(String)
+}
+
+impl Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Io(e) => write!(f, "couldn't access level file: {e}"),
+            LevelError::Json(e) => write!(f, "level file isn't valid json: {e}"),
+            LevelError::Config(e) => write!(f, "level file has invalid generator settings: {e}"),
+            LevelError::Invalid(message) => write!(f, "invalid level file: {message}")
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl LevelData {
+    /// for a brand new world, the player starts at the spawn looking along -z
+    pub fn new(config: WorldConfig, spawn: glm::Vec3) -> LevelData {
+        LevelData {
+            config,
+            spawn,
+            game_time: 0.0,
+            player_position: spawn,
+            player_direction: glm::vec3(0.0, 0.0, -1.0),
+            block_ids: Vec::new(),
+            save_settings: SaveSettings::default()
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<LevelData, LevelError> {
+        let text = std::fs::read_to_string(path).map_err(LevelError::Io)?;
+
+        LevelData::from_json(&JsonValue::parse(&text).map_err(LevelError::Json)?)
+    }
+
+    /// atomic, a crash while saving leaves the old file behind
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LevelError> {
+        save::write_atomic(path, self.to_json().to_string().as_bytes()).map_err(LevelError::Io)
+    }
+
+    /// unlike `WorldConfig` every field is required, older versions get upgraded first
+    pub fn from_json(json: &JsonValue) -> Result<LevelData, LevelError> {
+        let mut json = json.clone();
+        migration::migrate_level(&mut json).map_err(LevelError::Invalid)?;
+        let json = &json;
+
+        let field = |key: &str| json.get(key).ok_or_else(|| LevelError::Invalid(format!("missing \"{key}\"")));
+        let number = |key: &str| field(key)?.as_f64().ok_or_else(|| LevelError::Invalid(format!("\"{key}\" should be a number")));
+
+        let mut config = WorldConfig::from_json(field("generator")?).map_err(LevelError::Config)?;
+        let seed = number("seed")?;
+        if seed.fract() != 0.0 || !(0.0..=u32::MAX as f64).contains(&seed) {
+            return Err(LevelError::Invalid("\"seed\" should be a whole number from 0 to 4294967295".to_string()));
+        }
+        config.seed = seed as u32;
+
+        let player = field("player")?;
+
+        Ok(LevelData {
+            config,
+            spawn: vec3_from_json(field("spawn")?, "spawn")?,
+            game_time: number("game_time")?,
+            player_position: vec3_from_json(player.get("position").ok_or_else(|| LevelError::Invalid("player is missing \"position\"".to_string()))?, "player position")?,
+            player_direction: vec3_from_json(player.get("direction").ok_or_else(|| LevelError::Invalid("player is missing \"direction\"".to_string()))?, "player direction")?,
+            block_ids: field("block_ids")?
+                .as_array()
+                .and_then(|ids| ids.iter().map(|id| id.as_str().map(|id| id.to_string())).collect::<Option<Vec<_>>>())
+                .ok_or_else(|| LevelError::Invalid("\"block_ids\" should be an array of dev names".to_string()))?,
+            save_settings: SaveSettings::from_json(field("save")?).map_err(LevelError::Invalid)?
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        // the seed gets its own field so its easy to find
+        let generator = match self.config.to_json() {
+            JsonValue::Object(fields) => JsonValue::Object(fields.into_iter().filter(|(key, _)| key != "seed").collect()),
+            json => json
+        };
+
+        JsonValue::Object(vec![
+            ("version".to_string(), JsonValue::Number(migration::LEVEL_VERSION as f64)),
+            ("seed".to_string(), JsonValue::Number(self.config.seed as f64)),
+            ("generator".to_string(), generator),
+            ("spawn".to_string(), vec3_to_json(&self.spawn)),
+            ("game_time".to_string(), JsonValue::Number(self.game_time)),
+            ("player".to_string(), JsonValue::Object(vec![
+                ("position".to_string(), vec3_to_json(&self.player_position)),
+                ("direction".to_string(), vec3_to_json(&self.player_direction))
+            ])),
+            ("block_ids".to_string(), JsonValue::Array(self.block_ids.iter().map(|id| JsonValue::String(id.clone())).collect())),
+            ("save".to_string(), self.save_settings.to_json())
+        ])
+    }
+}
+
+fn vec3_from_json(json: &JsonValue, name: &str) -> Result<glm::Vec3, LevelError> {
+    match json.as_array().map(|values| values.iter().map(|v| v.as_f64()).collect::<Option<Vec<_>>>()) {
+        Some(Some(values)) if values.len() == 3 => Ok(glm::vec3(values[0] as f32, values[1] as f32, values[2] as f32)),
+        _ => Err(LevelError::Invalid(format!("{name} should be an array of 3 numbers")))
+    }
+}
+
+fn vec3_to_json(vec: &glm::Vec3) -> JsonValue {
+    JsonValue::Array(vec.iter().map(|v| JsonValue::Number(*v as f64)).collect())
+}
@@ -0,0 +1,540 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use super::{block::{Block, BlockId}, chunk::{Chunk, GlobalPos}, registry::BlockRegistry, ChunkPos};
+
+/// light levels go from 0 (dark) to this
+pub const MAX_LIGHT: u8 = 15;
+
+/// every channel spreads on its own. block light is split into red, green and blue so where
+/// coloured lights overlap each colour keeps the brighter of the two and they mix.
+/// sky light is always white and doesnt mix with block light, the shader takes whichever is brighter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LightChannel {
+    Sky,
+    Red,
+    Green,
+    Blue
+}
+
+impl LightChannel {
+    pub const ALL: [LightChannel; 4] = [LightChannel::Sky, LightChannel::Red, LightChannel::Green, LightChannel::Blue];
+    /// in the order of `Block::emission`
+    pub const BLOCK: [LightChannel; 3] = [LightChannel::Red, LightChannel::Green, LightChannel::Blue];
+
+    /// where the channel is in `ChunkLight::levels`
+    fn shift(self) -> u32 {
+        self as u32 * 4
+    }
+}
+
+/// the light of every block in a chunk, indexed like its blocks
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkLight {
+    /// 4 bits per channel in the order of `LightChannel::ALL`, starting at the lowest
+    levels: Box<[u16]>
+}
+
+impl ChunkLight {
+    /// completely dark
+    pub fn new() -> ChunkLight {
+        ChunkLight {
+            levels: vec![0; Chunk::VOLUME].into_boxed_slice()
+        }
+    }
+
+    pub fn get(&self, index: usize, channel: LightChannel) -> u8 {
+        ((self.levels[index] >> channel.shift()) & 0xF) as u8
+    }
+
+    pub fn set(&mut self, index: usize, channel: LightChannel, level: u8) {
+        let level = level.min(MAX_LIGHT) as u16;
+        self.levels[index] = (self.levels[index] & !(0xF << channel.shift())) | (level << channel.shift());
+    }
+}
+
+impl Default for ChunkLight {
+    fn default() -> ChunkLight {
+        ChunkLight::new()
+    }
+}
+
+/// indexed like `mesher::Face::ALL`
+const DIRECTIONS: [GlobalPos; 6] = [
+    glm::IVec3::new(-1, 0, 0),
+    glm::IVec3::new(1, 0, 0),
+    glm::IVec3::new(0, 1, 0),
+    glm::IVec3::new(0, -1, 0),
+    glm::IVec3::new(0, 0, -1),
+    glm::IVec3::new(0, 0, 1)
+];
+const DOWN: GlobalPos = DIRECTIONS[3];
+
+/// spreads light through the loaded chunks with a flood fill, chunks that arent loaded are dark and dont let light through
+///
+/// sky light comes in from above where theres no chunk loaded above the block and the generator says its above the surface.
+/// it goes straight down without getting darker until it hits something, everything else loses at least 1 per block.
+///
+/// light that came in from a chunk thats unloaded later stays where it is, its what the chunk would still get if it was there
+pub struct LightUpdate<'a> {
+    chunks: &'a mut HashMap<ChunkPos, Chunk>,
+    registry: &'a BlockRegistry,
+    /// `TerrainGenerator::surface_height`
+    surface_height: &'a dyn Fn(i32, i32) -> Option<i32>,
+    /// positions of the chunks whose meshes depend on light that changed
+    changed_chunks: HashSet<GlobalPos>
+}
+
+impl<'a> LightUpdate<'a> {
+    pub fn new(chunks: &'a mut HashMap<ChunkPos, Chunk>, registry: &'a BlockRegistry, surface_height: &'a dyn Fn(i32, i32) -> Option<i32>) -> LightUpdate<'a> {
+        LightUpdate {
+            chunks,
+            registry,
+            surface_height,
+            changed_chunks: HashSet::new()
+        }
+    }
+
+    /// the chunks that need new meshes, which are the ones whose light changed and the neighbours
+    /// that have faces looking into them
+    pub fn changed_chunks(self) -> HashSet<GlobalPos> {
+        self.changed_chunks
+    }
+
+    /// lights a chunk that just got loaded (and already has all of its blocks), and lets its light into the loaded chunks around it
+    pub fn light_chunk(&mut self, key: ChunkPos) {
+        let size = Chunk::SIZE as i32;
+        let chunk_position = key * size;
+        let last = size - 1;
+
+        for channel in LightChannel::ALL {
+            let mut queue = VecDeque::new();
+
+            // only the top can have sky light coming in, below that the sky light comes from spreading
+            let sources = match channel {
+                LightChannel::Sky => (0..size * size).map(|i| chunk_position + glm::vec3(i % size, last, i / size)).collect::<Vec<_>>(),
+                _ => {
+                    let blocks = self.chunks.get(&key).unwrap().blocks();
+                    let emits = |block: &BlockId| emission(self.registry.get(*block), channel) > 0;
+
+                    // most chunks dont have anything that gives off light
+                    if blocks.palette().iter().any(emits) {
+                        (0..Chunk::VOLUME).filter(|i| emits(blocks.get(*i))).map(|i| {
+                            let local_pos = Chunk::local_pos(i);
+                            chunk_position + glm::vec3(local_pos.x as i32, local_pos.y as i32, local_pos.z as i32)
+                        }).collect()
+                    } else {
+                        Vec::new()
+                    }
+                }
+            };
+            for global_pos in sources {
+                let source = self.source(global_pos, channel);
+                if source > 0 {
+                    self.set(global_pos, channel, source);
+                    queue.push_back(global_pos);
+                }
+            }
+
+            // light of the neighbours that can now spread into this chunk
+            for direction in DIRECTIONS {
+                if !self.chunks.contains_key(&(key + direction)) {
+                    continue;
+                }
+
+                // the layer of the neighbour thats right against this chunk
+                let axis = (0..3).find(|axis| direction[*axis] != 0).unwrap();
+                for i in 0..size * size {
+                    let mut border = glm::vec3(0, 0, 0);
+                    border[axis] = if direction[axis] > 0 { size } else { -1 };
+                    border[(axis + 1) % 3] = i % size;
+                    border[(axis + 2) % 3] = i / size;
+                    let global_pos = chunk_position + border;
+
+                    if self.get(global_pos, channel).unwrap() > 0 {
+                        queue.push_back(global_pos);
+                    }
+                }
+            }
+
+            self.spread(queue, channel);
+        }
+
+        // the top of the chunk below could have sky light that came in from above while this chunk wasnt there
+        let below = key + DOWN;
+        if self.chunks.contains_key(&below) {
+            let mut too_bright = Vec::new();
+            for i in 0..size * size {
+                let top = chunk_position + glm::vec3(i % size, -1, i / size);
+                let from_above = spread(self.get(top - DOWN, LightChannel::Sky).unwrap(), DOWN, self.block(top).unwrap(), LightChannel::Sky);
+
+                if self.get(top, LightChannel::Sky).unwrap() > from_above {
+                    too_bright.push(top);
+                }
+            }
+
+            self.remove(&too_bright, LightChannel::Sky);
+        }
+    }
+
+    /// updates the light after the blocks at `positions` changed, they all have to be in loaded chunks
+    pub fn update_blocks(&mut self, positions: &[GlobalPos]) {
+        for channel in LightChannel::ALL {
+            self.remove(positions, channel);
+        }
+    }
+
+    /// None if the chunk `global_pos` is in isnt loaded
+    fn get(&self, global_pos: GlobalPos, channel: LightChannel) -> Option<u8> {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        self.chunks.get(&(chunk_position / Chunk::SIZE as i32)).map(|chunk| chunk.get_light(local_pos, channel).unwrap())
+    }
+
+    fn set(&mut self, global_pos: GlobalPos, channel: LightChannel, level: u8) {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        self.chunks.get_mut(&(chunk_position / Chunk::SIZE as i32)).unwrap().set_light(local_pos, channel, level);
+
+        // the faces of the neighbours on this side look at this block for their light
+        self.changed_chunks.insert(chunk_position);
+        let last = Chunk::SIZE as i8 - 1;
+        for axis in 0..3 {
+            let mut direction = glm::vec3(0, 0, 0);
+            if local_pos[axis] == 0 {
+                direction[axis] = -1;
+            } else if local_pos[axis] == last {
+                direction[axis] = 1;
+            } else {
+                continue;
+            }
+
+            self.changed_chunks.insert(chunk_position + direction * Chunk::SIZE as i32);
+        }
+    }
+
+    /// None if the chunk `global_pos` is in isnt loaded
+    fn block(&self, global_pos: GlobalPos) -> Option<&'a Block> {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        let block = self.chunks.get(&(chunk_position / Chunk::SIZE as i32))?.get_block(local_pos).unwrap();
+
+        Some(self.registry.get(block))
+    }
+
+    /// the light a block has no matter what is around it
+    fn source(&self, global_pos: GlobalPos, channel: LightChannel) -> u8 {
+        let block = self.block(global_pos).unwrap();
+
+        match channel {
+            LightChannel::Sky => {
+                let above = global_pos - DOWN;
+                let sky_above = self.block(above).is_none() && (self.surface_height)(above.x, above.z).is_none_or(|height| above.y >= height);
+
+                if sky_above { spread(MAX_LIGHT, DOWN, block, channel) } else { 0 }
+            }
+            _ => emission(block, channel)
+        }
+    }
+
+    /// flood fills from every position in `queue` until the light cant get any further
+    fn spread(&mut self, mut queue: VecDeque<GlobalPos>, channel: LightChannel) {
+        while let Some(global_pos) = queue.pop_front() {
+            let level = self.get(global_pos, channel).unwrap();
+
+            for direction in DIRECTIONS {
+                let neighbour = global_pos + direction;
+                let Some(block) = self.block(neighbour) else {
+                    continue;
+                };
+
+                let spread_level = spread(level, direction, block, channel);
+                if spread_level > self.get(neighbour, channel).unwrap() {
+                    self.set(neighbour, channel, spread_level);
+                    queue.push_back(neighbour);
+                }
+            }
+        }
+    }
+
+    /// takes away the light at `positions` and all the light that could have come from there,
+    /// then fills it in again from whatever light is left around it
+    fn remove(&mut self, positions: &[GlobalPos], channel: LightChannel) {
+        let mut removing = VecDeque::new();
+        for global_pos in positions {
+            let level = self.get(*global_pos, channel).unwrap();
+            self.set(*global_pos, channel, 0);
+            removing.push_back((*global_pos, level));
+        }
+
+        let mut removed = Vec::new();
+        let mut refill = VecDeque::new();
+        while let Some((global_pos, level)) = removing.pop_front() {
+            removed.push(global_pos);
+
+            for direction in DIRECTIONS {
+                let neighbour = global_pos + direction;
+                let Some(block) = self.block(neighbour) else {
+                    continue;
+                };
+
+                let neighbour_level = self.get(neighbour, channel).unwrap();
+                if neighbour_level == 0 {
+                    continue;
+                }
+
+                // anything brighter than what could have come from here has its own way of getting light
+                if neighbour_level <= spread(level, direction, block, channel) {
+                    self.set(neighbour, channel, 0);
+                    removing.push_back((neighbour, neighbour_level));
+                } else {
+                    refill.push_back(neighbour);
+                }
+            }
+        }
+
+        // sources are only put back once everything around them is gone, otherwise they could get removed again
+        for global_pos in removed {
+            let source = self.source(global_pos, channel);
+            if source > self.get(global_pos, channel).unwrap() {
+                self.set(global_pos, channel, source);
+                refill.push_back(global_pos);
+            }
+        }
+
+        self.spread(refill, channel);
+    }
+}
+
+/// how bright `block` is in one of the colours of block light, 0 for sky light
+fn emission(block: &Block, channel: LightChannel) -> u8 {
+    LightChannel::BLOCK.iter().position(|c| *c == channel).map_or(0, |i| block.emission()[i])
+}
+
+/// how bright light at `level` is after going one block in `direction` into `block`
+fn spread(level: u8, direction: GlobalPos, block: &Block, channel: LightChannel) -> u8 {
+    if channel == LightChannel::Sky && direction == DOWN && level == MAX_LIGHT && block.opacity == 0 {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(1 + block.opacity)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::world::{block::{Block, BlockId, BlockType}, chunk::{Chunk, GlobalPos}, registry::BlockRegistry, ChunkPos};
+    use super::{LightChannel, LightUpdate, MAX_LIGHT};
+
+    const STONE: BlockId = BlockId(1);
+    const LAMP: BlockId = BlockId(2);
+    const LEAVES: BlockId = BlockId(3);
+    const RED_LAMP: BlockId = BlockId(4);
+    const BLUE_LAMP: BlockId = BlockId(5);
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::new();
+        registry.register(Block::new("Stone", "stone", BlockType::Solid, glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0)));
+
+        let mut lamp = Block::new("Lamp", "lamp", BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0));
+        lamp.light_emission = MAX_LIGHT;
+        registry.register(lamp);
+
+        let mut leaves = Block::new("Leaves", "leaves", BlockType::Solid, glm::vec2(0.2, 0.1), glm::vec2(0.2, 0.1), glm::vec2(0.2, 0.1));
+        leaves.opacity = 2;
+        registry.register(leaves);
+
+        for (name, light_color) in [("red_lamp", [255, 64, 0]), ("blue_lamp", [0, 0, 255])] {
+            let mut lamp = Block::new(name, name, BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0));
+            lamp.light_emission = 12;
+            lamp.light_color = light_color;
+            registry.register(lamp);
+        }
+
+        registry
+    }
+
+    /// no surface anywhere, so theres sky light wherever there isnt a chunk above
+    fn open_sky(_x: i32, _z: i32) -> Option<i32> {
+        None
+    }
+
+    /// the surface is so high up that no chunk gets any sky light
+    fn underground(_x: i32, _z: i32) -> Option<i32> {
+        Some(1000)
+    }
+
+    fn chunks(keys: &[ChunkPos], chunk_gen: impl Fn(GlobalPos) -> BlockId) -> HashMap<ChunkPos, Chunk> {
+        keys.iter().map(|key| (*key, Chunk::new(key * Chunk::SIZE as i32, &chunk_gen))).collect()
+    }
+
+    /// lights `keys` in that order
+    fn light(chunks: &mut HashMap<ChunkPos, Chunk>, registry: &BlockRegistry, surface_height: fn(i32, i32) -> Option<i32>, keys: &[ChunkPos]) {
+        let mut update = LightUpdate::new(chunks, registry, &surface_height);
+        for key in keys {
+            update.light_chunk(*key);
+        }
+    }
+
+    fn set_block(chunks: &mut HashMap<ChunkPos, Chunk>, registry: &BlockRegistry, surface_height: fn(i32, i32) -> Option<i32>, global_pos: GlobalPos, block: BlockId) {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        chunks.get_mut(&(chunk_position / Chunk::SIZE as i32)).unwrap().set_block(local_pos, block);
+
+        LightUpdate::new(chunks, registry, &surface_height).update_blocks(&[global_pos]);
+    }
+
+    fn get(chunks: &HashMap<ChunkPos, Chunk>, global_pos: GlobalPos, channel: LightChannel) -> u8 {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        chunks.get(&(chunk_position / Chunk::SIZE as i32)).unwrap().get_light(local_pos, channel).unwrap()
+    }
+
+    fn all_light(chunks: &HashMap<ChunkPos, Chunk>) -> Vec<(ChunkPos, Vec<u8>)> {
+        let mut light = chunks.iter().map(|(key, chunk)| {
+            (*key, (0..Chunk::VOLUME).flat_map(|i| LightChannel::ALL.map(|channel| chunk.get_light(Chunk::local_pos(i), channel).unwrap())).collect())
+        }).collect::<Vec<_>>();
+        light.sort_by_key(|(key, _)| (key.x, key.y, key.z));
+
+        light
+    }
+
+    #[test]
+    fn sky_light_goes_straight_down() {
+        let registry = registry();
+        let key = glm::vec3(0, 0, 0);
+        let mut chunks = chunks(&[key], |_| BlockId::AIR);
+        light(&mut chunks, &registry, open_sky, &[key]);
+
+        assert!((0..Chunk::VOLUME).all(|i| chunks[&key].get_light(Chunk::local_pos(i), LightChannel::Sky) == Some(MAX_LIGHT)));
+
+        // the column below the block only gets what comes in from the side
+        set_block(&mut chunks, &registry, open_sky, glm::vec3(10, 15, 10), STONE);
+        assert_eq!(get(&chunks, glm::vec3(10, 15, 10), LightChannel::Sky), 0);
+        for y in 0..15 {
+            assert_eq!(get(&chunks, glm::vec3(10, y, 10), LightChannel::Sky), MAX_LIGHT - 1);
+        }
+        assert_eq!(get(&chunks, glm::vec3(11, 14, 10), LightChannel::Sky), MAX_LIGHT);
+
+        set_block(&mut chunks, &registry, open_sky, glm::vec3(10, 15, 10), BlockId::AIR);
+        assert!((0..Chunk::VOLUME).all(|i| chunks[&key].get_light(Chunk::local_pos(i), LightChannel::Sky) == Some(MAX_LIGHT)));
+    }
+
+    #[test]
+    fn block_light_fades_by_one_per_block() {
+        let registry = registry();
+        let key = glm::vec3(0, 0, 0);
+        let mut chunks = chunks(&[key], |pos| if pos == glm::vec3(10, 10, 10) { LAMP } else { BlockId::AIR });
+        light(&mut chunks, &registry, underground, &[key]);
+
+        assert_eq!(get(&chunks, glm::vec3(10, 10, 10), LightChannel::Red), MAX_LIGHT);
+        assert_eq!(get(&chunks, glm::vec3(13, 10, 10), LightChannel::Red), MAX_LIGHT - 3);
+        assert_eq!(get(&chunks, glm::vec3(11, 11, 11), LightChannel::Red), MAX_LIGHT - 3);
+        assert_eq!(get(&chunks, glm::vec3(0, 0, 0), LightChannel::Red), 0);
+        assert_eq!(get(&chunks, glm::vec3(5, 10, 10), LightChannel::Sky), 0);
+
+        set_block(&mut chunks, &registry, underground, glm::vec3(10, 10, 10), BlockId::AIR);
+        assert!((0..Chunk::VOLUME).all(|i| chunks[&key].get_light(Chunk::local_pos(i), LightChannel::Red) == Some(0)));
+    }
+
+    #[test]
+    fn coloured_lights_mix() {
+        let registry = registry();
+        let key = glm::vec3(0, 0, 0);
+        let mut chunks = chunks(&[key], |pos| match (pos.x, pos.y, pos.z) {
+            (5, 10, 10) => RED_LAMP,
+            (9, 10, 10) => BLUE_LAMP,
+            _ => BlockId::AIR
+        });
+        light(&mut chunks, &registry, underground, &[key]);
+
+        let rgb = |chunks: &HashMap<ChunkPos, Chunk>, global_pos| LightChannel::BLOCK.map(|channel| get(chunks, global_pos, channel));
+        assert_eq!(rgb(&chunks, glm::vec3(5, 10, 10)), [12, 3, 0]);
+        assert_eq!(rgb(&chunks, glm::vec3(7, 10, 10)), [10, 1, 10]);
+        assert_eq!(rgb(&chunks, glm::vec3(9, 11, 10)), [7, 0, 11]);
+
+        // taking one of them away leaves the other one alone
+        set_block(&mut chunks, &registry, underground, glm::vec3(5, 10, 10), BlockId::AIR);
+        assert_eq!(rgb(&chunks, glm::vec3(7, 10, 10)), [0, 0, 10]);
+        assert_eq!(rgb(&chunks, glm::vec3(9, 10, 10)), [0, 0, 12]);
+    }
+
+    #[test]
+    fn opacity_takes_away_more_light() {
+        let registry = registry();
+        let key = glm::vec3(0, 0, 0);
+        let mut chunks = chunks(&[key], |pos| match (pos.x, pos.y, pos.z) {
+            (10, 10, 10) => LAMP,
+            (11, 10, 10) => LEAVES,
+            _ => BlockId::AIR
+        });
+        light(&mut chunks, &registry, underground, &[key]);
+
+        assert_eq!(get(&chunks, glm::vec3(11, 10, 10), LightChannel::Red), MAX_LIGHT - 3);
+        assert_eq!(get(&chunks, glm::vec3(9, 10, 10), LightChannel::Red), MAX_LIGHT - 1);
+
+        // stone doesnt let anything through, the light has to go around it
+        set_block(&mut chunks, &registry, underground, glm::vec3(11, 10, 10), STONE);
+        assert_eq!(get(&chunks, glm::vec3(11, 10, 10), LightChannel::Red), 0);
+        assert_eq!(get(&chunks, glm::vec3(12, 10, 10), LightChannel::Red), MAX_LIGHT - 4);
+    }
+
+    #[test]
+    fn light_crosses_chunk_borders_in_any_load_order() {
+        let registry = registry();
+        let keys = [glm::vec3(0, 0, 0), glm::vec3(1, 0, 0)];
+        let chunk_gen = |pos: GlobalPos| if pos == glm::vec3(18, 10, 10) { LAMP } else { BlockId::AIR };
+
+        let mut in_order = chunks(&keys, chunk_gen);
+        light(&mut in_order, &registry, underground, &keys);
+        let mut reversed = chunks(&keys, chunk_gen);
+        light(&mut reversed, &registry, underground, &[keys[1], keys[0]]);
+
+        assert_eq!(get(&in_order, glm::vec3(20, 10, 10), LightChannel::Red), MAX_LIGHT - 2);
+        assert_eq!(get(&in_order, glm::vec3(25, 10, 10), LightChannel::Red), MAX_LIGHT - 7);
+        assert_eq!(all_light(&in_order), all_light(&reversed));
+    }
+
+    #[test]
+    fn roof_in_the_chunk_above_darkens_the_chunk_below() {
+        let registry = registry();
+        let (below, above) = (glm::vec3(0, 0, 0), glm::vec3(0, 1, 0));
+        let chunk_gen = |pos: GlobalPos| if pos.y == 20 { STONE } else { BlockId::AIR };
+
+        let mut chunks = chunks(&[below], chunk_gen);
+        light(&mut chunks, &registry, open_sky, &[below]);
+        assert_eq!(get(&chunks, glm::vec3(5, 0, 5), LightChannel::Sky), MAX_LIGHT);
+
+        chunks.insert(above, Chunk::new(above * Chunk::SIZE as i32, chunk_gen));
+        light(&mut chunks, &registry, open_sky, &[above]);
+        assert!((0..Chunk::VOLUME).all(|i| chunks[&below].get_light(Chunk::local_pos(i), LightChannel::Sky) == Some(0)));
+        assert_eq!(get(&chunks, glm::vec3(5, 21, 5), LightChannel::Sky), MAX_LIGHT);
+    }
+
+    #[test]
+    fn edits_end_up_like_lighting_from_scratch() {
+        let registry = registry();
+        let keys = [glm::vec3(0, 0, 0), glm::vec3(1, 0, 0), glm::vec3(0, 1, 0), glm::vec3(1, 1, 0), glm::vec3(0, 0, 1), glm::vec3(1, 1, 1)];
+        let hash = |pos: GlobalPos, seed: u32| {
+            let mut hash = seed ^ (pos.x as u32).wrapping_mul(73856093) ^ (pos.y as u32).wrapping_mul(19349663) ^ (pos.z as u32).wrapping_mul(83492791);
+            hash ^= hash >> 13;
+            hash = hash.wrapping_mul(0x5bd1e995);
+            hash ^ (hash >> 15)
+        };
+        let random_block = |pos: GlobalPos, seed: u32| match hash(pos, seed) % 60 {
+            0 => LAMP,
+            1 => RED_LAMP,
+            2 => BLUE_LAMP,
+            3..=6 => LEAVES,
+            7..=24 => STONE,
+            _ => BlockId::AIR
+        };
+
+        let mut chunks = chunks(&keys, |pos| random_block(pos, 1));
+        light(&mut chunks, &registry, open_sky, &keys);
+
+        for i in 0..200 {
+            let pos = glm::vec3(hash(glm::vec3(i, 0, 0), 2) % 40, hash(glm::vec3(i, 1, 0), 2) % 40, hash(glm::vec3(i, 2, 0), 2) % 20);
+            let pos = glm::vec3(pos.x as i32, pos.y as i32, pos.z as i32);
+            set_block(&mut chunks, &registry, open_sky, pos, random_block(pos, 3 + i as u32));
+        }
+
+        let mut from_scratch = chunks.iter().map(|(key, chunk)| (*key, Chunk::from_blocks(chunk.position(), chunk.blocks().clone()))).collect();
+        light(&mut from_scratch, &registry, open_sky, &keys);
+
+        assert!(all_light(&chunks) == all_light(&from_scratch), "light after edits is different from lighting the chunks again");
+    }
+}
@@ -0,0 +1,835 @@
+use crate::engine::vertex::ChunkVertex;
+use super::{block::{Block, BlockId, BlockType}, chunk::{Chunk, LocalPos}, light::{LightChannel, MAX_LIGHT}, registry::BlockRegistry};
+
+/// the atlas is this many tiles wide and high, `default.frag` has the same number
+pub const ATLAS_TILES: u16 = 10;
+
+/// one vertex for every corner, going bottom left, bottom right, top right, top left
+pub const VERTICES_PER_QUAD: u64 = 4;
+pub const INDICES_PER_QUAD: u64 = 6;
+/// the two triangles of a quad, every quad has the same ones so a single index buffer works for all chunks
+const QUAD_INDICES: [u32; INDICES_PER_QUAD as usize] = [0, 2, 3, 0, 1, 2];
+
+/// indices for the first `quad_count` quads of any mesh, as u32 since a chunk can have more than 65535 vertices
+pub fn quad_indices(quad_count: u64) -> Vec<u32> {
+    (0..quad_count as u32).flat_map(|quad| QUAD_INDICES.map(|index| quad * VERTICES_PER_QUAD as u32 + index)).collect()
+}
+
+/// the sides of a block, in the order `build_mesh` wants the neighbour chunks in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    West,
+    East,
+    Up,
+    Down,
+    North,
+    South
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::West, Face::East, Face::Up, Face::Down, Face::North, Face::South];
+
+    /// which way the face points in block space
+    pub fn normal(&self) -> LocalPos {
+        match self {
+            Face::West => glm::vec3(-1, 0, 0),
+            Face::East => glm::vec3(1, 0, 0),
+            Face::Up => glm::vec3(0, 1, 0),
+            Face::Down => glm::vec3(0, -1, 0),
+            Face::North => glm::vec3(0, 0, -1),
+            Face::South => glm::vec3(0, 0, 1)
+        }
+    }
+
+    /// corners of the face relative to the blocks vertex position, in the order their uvs go:
+    /// bottom left, bottom right, top right, top left
+    fn corners(&self) -> [glm::Vec3; 4] {
+        match self {
+            Face::West => [glm::vec3(0.0, 0.0, -1.0), glm::vec3(0.0, 0.0, 0.0), glm::vec3(0.0, 1.0, 0.0), glm::vec3(0.0, 1.0, -1.0)],
+            Face::East => [glm::vec3(1.0, 0.0, 0.0), glm::vec3(1.0, 0.0, -1.0), glm::vec3(1.0, 1.0, -1.0), glm::vec3(1.0, 1.0, 0.0)],
+            Face::Up => [glm::vec3(0.0, 1.0, 0.0), glm::vec3(1.0, 1.0, 0.0), glm::vec3(1.0, 1.0, -1.0), glm::vec3(0.0, 1.0, -1.0)],
+            Face::Down => [glm::vec3(0.0, 0.0, -1.0), glm::vec3(1.0, 0.0, -1.0), glm::vec3(1.0, 0.0, 0.0), glm::vec3(0.0, 0.0, 0.0)],
+            Face::North => [glm::vec3(1.0, 0.0, -1.0), glm::vec3(0.0, 0.0, -1.0), glm::vec3(0.0, 1.0, -1.0), glm::vec3(1.0, 1.0, -1.0)],
+            Face::South => [glm::vec3(0.0, 0.0, 0.0), glm::vec3(1.0, 0.0, 0.0), glm::vec3(1.0, 1.0, 0.0), glm::vec3(0.0, 1.0, 0.0)]
+        }
+    }
+
+    /// the block at (`a`, `b`) of the `layer`th slice of the chunk facing this way,
+    /// `a` goes the way the texture goes right and `b` the way it goes up
+    fn slice_pos(&self, layer: i8, a: i8, b: i8) -> LocalPos {
+        let corners = self.corners();
+        let right = corners[1] - corners[0];
+        let up = corners[3] - corners[0];
+        let normal = self.normal();
+        let last = Chunk::SIZE as i8 - 1;
+
+        glm::vec3(0, 1, 2).map(|axis| {
+            if normal[axis] != 0 {
+                layer
+            } else if right[axis] != 0.0 {
+                if right[axis] > 0.0 { a } else { last - a }
+            } else if up[axis] > 0.0 {
+                b
+            } else {
+                last - b
+            }
+        })
+    }
+
+    /// index of the texture this side of `block` uses in the atlas
+    fn tile(&self, block: &Block) -> u16 {
+        let uv = match self {
+            Face::Up => block.top_uv,
+            Face::Down => block.bottom_uv,
+            _ => block.side_uv
+        };
+
+        let tiles = ATLAS_TILES as f32;
+        (uv.x * tiles).round() as u16 + (uv.y * tiles).round() as u16 * ATLAS_TILES
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MeshingMode {
+    /// one quad for every visible face
+    Naive,
+    /// faces next to each other with the same texture get merged into bigger quads
+    #[default]
+    Greedy
+}
+
+impl MeshingMode {
+    /// the other one, for switching between them in game
+    pub fn toggled(&self) -> MeshingMode {
+        match self {
+            MeshingMode::Naive => MeshingMode::Greedy,
+            MeshingMode::Greedy => MeshingMode::Naive
+        }
+    }
+}
+
+/// everything `build_mesh` made for one chunk, positions are relative to the chunk (before the model matrix)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkMesh {
+    /// solid and cutout blocks
+    pub vertices: Vec<ChunkVertex>,
+    /// translucent blocks, these get drawn after everything else and have to be put in order
+    /// with `sort_back_to_front` first
+    pub translucent: Vec<ChunkVertex>,
+    /// (min, max) of all the vertices, None if the mesh is empty
+    pub bounds: Option<(glm::Vec3, glm::Vec3)>,
+    /// how many quads point each way, indexed like `Face::ALL`, with `MeshingMode::Greedy` one quad can be many faces
+    pub face_counts: [u32; 6]
+}
+
+impl ChunkMesh {
+    pub fn face_count(&self) -> u32 {
+        self.face_counts.iter().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty() && self.translucent.is_empty()
+    }
+
+    /// how many of the shared quad indices drawing `vertices` takes
+    pub fn index_count(&self) -> u64 {
+        index_count(&self.vertices)
+    }
+
+    /// how many of the shared quad indices drawing `translucent` takes
+    pub fn translucent_index_count(&self) -> u64 {
+        index_count(&self.translucent)
+    }
+}
+
+/// how many of the shared quad indices drawing `vertices` takes
+pub fn index_count(vertices: &[ChunkVertex]) -> u64 {
+    vertices.len() as u64 / VERTICES_PER_QUAD * INDICES_PER_QUAD
+}
+
+/// puts the quads in order from the one furthest away from `eye` to the closest, so blending them
+/// one after the other looks right. `eye` is in the same space as the vertices
+pub fn sort_back_to_front(vertices: &mut [ChunkVertex], eye: glm::Vec3) {
+    let mut quads = vertices.chunks_exact(VERTICES_PER_QUAD as usize).map(|quad| {
+        // the first and third corner are always across from each other
+        let center = (quad[0].position() + quad[2].position()) / 2.0;
+        (glm::distance2(&center, &eye), [quad[0], quad[1], quad[2], quad[3]])
+    }).collect::<Vec<_>>();
+    quads.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    for (i, (_, quad)) in quads.into_iter().enumerate() {
+        vertices[i * VERTICES_PER_QUAD as usize..(i + 1) * VERTICES_PER_QUAD as usize].copy_from_slice(&quad);
+    }
+}
+
+/// the chunks around the one being meshed, indexed with `neighbour_index`,
+/// None where a chunk isnt loaded (and in the middle where the chunk itself would be)
+pub type Neighbours<'a> = [Option<&'a Chunk>; 27];
+
+/// where the chunk `direction` (-1 to 1 on every axis) away goes in `Neighbours`
+pub fn neighbour_index(direction: glm::IVec3) -> usize {
+    ((direction.x + 1) + (direction.y + 1) * 3 + (direction.z + 1) * 9) as usize
+}
+
+/// the other way around from `neighbour_index`
+pub fn neighbour_direction(index: usize) -> glm::IVec3 {
+    let index = index as i32;
+    glm::vec3(index % 3 - 1, index / 3 % 3 - 1, index / 9 - 1)
+}
+
+/// the faces of every block that arent hidden by the block in front of them (see `visible_face`),
+/// doesnt touch the gpu so it can run on any thread (`Chunk::upload_mesh` does that part)
+///
+/// faces on the side of a missing neighbour are always generated. without `ambient_occlusion`
+/// every vertex gets `ChunkVertex::MAX_AO`, which also lets the greedy mesher merge more
+pub fn build_mesh(chunk: &Chunk, neighbours: &Neighbours, registry: &BlockRegistry, mode: MeshingMode, ambient_occlusion: bool) -> ChunkMesh {
+    let mut mesh = ChunkMesh {
+        vertices: Vec::new(),
+        translucent: Vec::new(),
+        bounds: None,
+        face_counts: [0; 6]
+    };
+
+    match mode {
+        MeshingMode::Naive => build_naive(&mut mesh, chunk, neighbours, registry, ambient_occlusion),
+        MeshingMode::Greedy => build_greedy(&mut mesh, chunk, neighbours, registry, ambient_occlusion)
+    }
+
+    mesh
+}
+
+fn build_naive(mesh: &mut ChunkMesh, chunk: &Chunk, neighbours: &Neighbours, registry: &BlockRegistry, ambient_occlusion: bool) {
+    for x in 0..Chunk::SIZE as i8 {
+        for y in 0..Chunk::SIZE as i8 {
+            for z in 0..Chunk::SIZE as i8 {
+                let local_pos = glm::vec3(x, y, z);
+
+                for i in 0..Face::ALL.len() {
+                    if let Some(look) = face_look(chunk, neighbours, registry, local_pos, i, ambient_occlusion) {
+                        push_quad(mesh, local_pos, i, 1, 1, look);
+                        mesh.face_counts[i] += 1;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// goes through the chunk one slice at a time for every face direction, and grows each visible face
+/// as far right as it can and then as far up as the whole row can go
+///
+/// only faces that look the same get merged (see `FaceLook`), so the shading stays the same
+fn build_greedy(mesh: &mut ChunkMesh, chunk: &Chunk, neighbours: &Neighbours, registry: &BlockRegistry, ambient_occlusion: bool) {
+    let size = Chunk::SIZE as usize;
+
+    for (i, face) in Face::ALL.iter().enumerate() {
+        for layer in 0..Chunk::SIZE as i8 {
+            // how the visible face at every spot in the slice looks, indexed by a + b * size
+            let mut faces = vec![None; size * size];
+            for b in 0..size {
+                for a in 0..size {
+                    let local_pos = face.slice_pos(layer, a as i8, b as i8);
+                    faces[a + b * size] = face_look(chunk, neighbours, registry, local_pos, i, ambient_occlusion);
+                }
+            }
+
+            for b in 0..size {
+                let mut a = 0;
+                while a < size {
+                    let Some(look) = faces[a + b * size] else {
+                        a += 1;
+                        continue;
+                    };
+
+                    let mut width = 1;
+                    while a + width < size && faces[a + width + b * size] == Some(look) {
+                        width += 1;
+                    }
+
+                    let mut height = 1;
+                    while b + height < size && (a..a + width).all(|a| faces[a + (b + height) * size] == Some(look)) {
+                        height += 1;
+                    }
+
+                    for b in b..b + height {
+                        faces[a + b * size..a + width + b * size].fill(None);
+                    }
+
+                    push_quad(mesh, face.slice_pos(layer, a as i8, b as i8), i, width as u32, height as u32, look);
+                    mesh.face_counts[i] += 1;
+
+                    a += width;
+                }
+            }
+        }
+    }
+}
+
+/// everything about a face that has to be the same for the greedy mesher to merge it with another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FaceLook {
+    tile: u16,
+    /// in the order of `Face::corners`
+    ao: [u8; 4],
+    /// (sky, red green and blue block light), the same for the whole face
+    light: (u8, [u8; 3]),
+    /// goes into `ChunkMesh::translucent`
+    translucent: bool
+}
+
+/// None if the face on side `face_index` of the block at `local_pos` cant be seen
+fn face_look(chunk: &Chunk, neighbours: &Neighbours, registry: &BlockRegistry, local_pos: LocalPos, face_index: usize, ambient_occlusion: bool) -> Option<FaceLook> {
+    visible_face(chunk, neighbours, registry, local_pos, face_index).map(|block| FaceLook {
+        tile: Face::ALL[face_index].tile(block),
+        ao: face_ao(chunk, neighbours, registry, local_pos, face_index, ambient_occlusion),
+        light: face_light(chunk, neighbours, local_pos, face_index),
+        translucent: block.block_type == BlockType::Translucent
+    })
+}
+
+/// the chunk `local_pos` is in and where it is in there, even if its just outside of `chunk`, None if the chunk its in isnt there
+fn locate<'a>(chunk: &'a Chunk, neighbours: &Neighbours<'a>, local_pos: LocalPos) -> Option<(&'a Chunk, LocalPos)> {
+    let size = Chunk::SIZE as i8;
+    let direction = local_pos.map(|v| v.div_euclid(size) as i32);
+
+    if direction == glm::vec3(0, 0, 0) {
+        Some((chunk, local_pos))
+    } else {
+        neighbours[neighbour_index(direction)].map(|neighbour| (neighbour, local_pos.map(|v| v.rem_euclid(size))))
+    }
+}
+
+/// the block at `local_pos` even if its just outside of the chunk, None if the chunk its in isnt there
+fn block_at(chunk: &Chunk, neighbours: &Neighbours, local_pos: LocalPos) -> Option<BlockId> {
+    locate(chunk, neighbours, local_pos).map(|(chunk, local_pos)| chunk.get_block(local_pos).unwrap())
+}
+
+/// (sky, red green and blue block light) of the block in front of the face on side `face_index` of the block at `local_pos`,
+/// faces looking into chunks that arent there get full sky light so the edge of the world isnt black
+fn face_light(chunk: &Chunk, neighbours: &Neighbours, local_pos: LocalPos, face_index: usize) -> (u8, [u8; 3]) {
+    match locate(chunk, neighbours, local_pos + Face::ALL[face_index].normal()) {
+        Some((chunk, local_pos)) => (chunk.get_light(local_pos, LightChannel::Sky).unwrap(), LightChannel::BLOCK.map(|channel| chunk.get_light(local_pos, channel).unwrap())),
+        None => (MAX_LIGHT, [0; 3])
+    }
+}
+
+/// the block at `local_pos` if it isnt air and the face on side `face_index` (of `Face::ALL`) can be seen
+///
+/// solid blocks hide every face behind them, cutout and translucent ones only hide the same block
+/// so theres no faces inside of a pool of water or a wall of glass
+fn visible_face<'a>(chunk: &Chunk, neighbours: &Neighbours, registry: &'a BlockRegistry, local_pos: LocalPos, face_index: usize) -> Option<&'a Block> {
+    let block_id = chunk.get_block(local_pos).unwrap();
+    let block = registry.get(block_id);
+    if block.block_type == BlockType::Air {
+        return None;
+    }
+
+    let normal = Face::ALL[face_index].normal();
+    let covered = block_at(chunk, neighbours, local_pos + normal).is_some_and(|neighbour_block| match registry.get(neighbour_block).block_type {
+        BlockType::Air => false,
+        BlockType::Solid => true,
+        BlockType::Cutout | BlockType::Translucent => neighbour_block == block_id
+    });
+
+    (!covered).then_some(block)
+}
+
+/// ao of the corners of the face on side `face_index` of the block at `local_pos`, in the order of `Face::corners`
+///
+/// each corner looks at the two blocks along its edges and the one diagonal to it in front of the face,
+/// with both edges blocked the corner is fully dark no matter whats in the diagonal
+fn face_ao(chunk: &Chunk, neighbours: &Neighbours, registry: &BlockRegistry, local_pos: LocalPos, face_index: usize, ambient_occlusion: bool) -> [u8; 4] {
+    if !ambient_occlusion {
+        return [ChunkVertex::MAX_AO; 4];
+    }
+
+    let face = Face::ALL[face_index];
+    let in_front = local_pos + face.normal();
+    let occludes = |offset: LocalPos| block_at(chunk, neighbours, in_front + offset).is_some_and(|block| registry.get(block).block_type == BlockType::Solid);
+
+    face.corners().map(|corner| {
+        // which way the corner is from the middle of the block (which is at 0.5, 0.5, -0.5), along the face
+        let side = (corner - glm::vec3(0.5, 0.5, -0.5)).zip_map(&face.normal(), |v, n| if n != 0 { 0 } else { v.signum() as i8 });
+        let first_axis = (0..3).find(|axis| side[*axis] != 0).unwrap();
+        let mut edge_a = glm::vec3(0, 0, 0);
+        edge_a[first_axis] = side[first_axis];
+        let edge_b = side - edge_a;
+
+        match (occludes(edge_a), occludes(edge_b)) {
+            (true, true) => 0,
+            (a, b) => ChunkVertex::MAX_AO - a as u8 - b as u8 - occludes(side) as u8
+        }
+    })
+}
+
+/// a quad covering `width` by `height` faces on side `face_index` (of `Face::ALL`) starting at the block at `local_pos`,
+/// the uvs count in tiles so the texture repeats once per block
+///
+/// the quad gets split into triangles along the diagonal thats darker, otherwise how the ao looks
+/// would depend on which way the quad is turned. the index buffer always splits between the first
+/// and third vertex so for the other diagonal the vertices start one corner later
+fn push_quad(mesh: &mut ChunkMesh, local_pos: LocalPos, face_index: usize, width: u32, height: u32, look: FaceLook) {
+    let corners = Face::ALL[face_index].corners();
+    let right = (corners[1] - corners[0]) * width as f32;
+    let up = (corners[3] - corners[0]) * height as f32;
+    let start = glm::vec3(local_pos.x as f32, local_pos.y as f32, local_pos.z as f32) + corners[0];
+
+    let positions = [start, start + right, start + right + up, start + up];
+    let uvs = [glm::vec2(0.0, 0.0), glm::vec2(width as f32, 0.0), glm::vec2(width as f32, height as f32), glm::vec2(0.0, height as f32)];
+
+    let ao = look.ao;
+    let first = if ao[0] + ao[2] > ao[1] + ao[3] { 1 } else { 0 };
+    for corner in (first..first + 4).map(|corner| corner % 4) {
+        let vertices = if look.translucent { &mut mesh.translucent } else { &mut mesh.vertices };
+        vertices.push(ChunkVertex::new(positions[corner], uvs[corner], face_index as u8, look.tile, ao[corner], look.light.0, look.light.1));
+
+        mesh.bounds = Some(match mesh.bounds {
+            Some((min, max)) => (glm::min2(&min, &positions[corner]), glm::max2(&max, &positions[corner])),
+            None => (positions[corner], positions[corner])
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::world::block::Block;
+    use super::*;
+
+    fn registry() -> BlockRegistry {
+        let mut registry = BlockRegistry::new();
+        registry.register(Block::new("Stone", "stone", BlockType::Solid, glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0)));
+        registry.register(Block::new("Grass Block", "grass_block", BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.1, 0.0), glm::vec2(0.2, 0.0)));
+        registry.register(Block::new("Glass", "glass", BlockType::Translucent, glm::vec2(0.4, 0.0), glm::vec2(0.4, 0.0), glm::vec2(0.4, 0.0)));
+        registry.register(Block::new("Leaves", "leaves", BlockType::Cutout, glm::vec2(0.5, 0.0), glm::vec2(0.5, 0.0), glm::vec2(0.5, 0.0)));
+
+        registry
+    }
+
+    /// a third of the blocks are air, the rest stone or grass
+    fn random_chunk(position: glm::IVec3, seed: u32) -> Chunk {
+        Chunk::new(position, |global_pos| {
+            let mut hash = seed ^ (global_pos.x as u32).wrapping_mul(73856093) ^ (global_pos.y as u32).wrapping_mul(19349663) ^ (global_pos.z as u32).wrapping_mul(83492791);
+            hash ^= hash >> 13;
+            hash = hash.wrapping_mul(0x5bd1e995);
+            hash ^= hash >> 15;
+
+            BlockId((hash % 3) as u16)
+        })
+    }
+
+    /// the vertices of every quad in the order of `Face::corners`, quads split along the other diagonal start one corner later
+    fn quads(mesh: &ChunkMesh) -> Vec<[ChunkVertex; 4]> {
+        mesh.vertices.chunks(VERTICES_PER_QUAD as usize).map(|quad| {
+            let first = quad.iter().position(|v| v.uv() == glm::vec2(0.0, 0.0)).unwrap();
+            std::array::from_fn(|corner| quad[(first + corner) % 4])
+        }).collect()
+    }
+
+    /// a block sized face as (face center * 2, normal, tile)
+    type UnitFace = ([i32; 3], [i32; 3], u16);
+
+    /// every block sized face a mesh covers, with the ao of its corners and how often its covered.
+    /// merged quads only have faces with the same ao so each face gets the ao of the quad
+    fn unit_faces_with_ao(mesh: &ChunkMesh) -> HashMap<UnitFace, ([u8; 4], u32)> {
+        let mut faces = HashMap::new();
+
+        for quad in quads(mesh) {
+            let (start, right_corner, up_corner) = (quad[0].position(), quad[1].position(), quad[3].position());
+            let (width, height) = (quad[1].uv().x, quad[3].uv().y);
+            let right = (right_corner - start) / width;
+            let up = (up_corner - start) / height;
+            let normal = right.cross(&up).map(|v| v.round() as i32);
+
+            for a in 0..width as i32 {
+                for b in 0..height as i32 {
+                    let center = (start + right * (a as f32 + 0.5) + up * (b as f32 + 0.5)) * 2.0;
+                    let key = ([center.x.round() as i32, center.y.round() as i32, center.z.round() as i32], [normal.x, normal.y, normal.z], quad[0].tile());
+                    faces.entry(key).or_insert((quad.map(|v| v.ao()), 0)).1 += 1;
+                }
+            }
+        }
+
+        faces
+    }
+
+    /// like `unit_faces_with_ao` without the ao
+    fn unit_faces(mesh: &ChunkMesh) -> HashMap<UnitFace, u32> {
+        unit_faces_with_ao(mesh).into_iter().map(|(key, (_, count))| (key, count)).collect()
+    }
+
+    /// only the chunks that are in `chunks`, by direction
+    fn neighbours<'a>(chunks: &[(glm::IVec3, &'a Chunk)]) -> Neighbours<'a> {
+        let mut neighbours = [None; 27];
+        for (direction, chunk) in chunks {
+            neighbours[neighbour_index(*direction)] = Some(*chunk);
+        }
+
+        neighbours
+    }
+
+    #[test]
+    fn greedy_covers_the_same_faces_as_naive() {
+        let registry = registry();
+
+        for seed in 0..12 {
+            let chunk = random_chunk(glm::vec3(0, 0, 0), seed);
+            let neighbour_chunks = (0..27).map(|i| random_chunk(neighbour_direction(i) * Chunk::SIZE as i32, seed)).collect::<Vec<_>>();
+            // some neighbours missing so both kinds of border get tested
+            let neighbours = std::array::from_fn(|i| (i != 13 && !(i as u32 + seed).is_multiple_of(3)).then_some(&neighbour_chunks[i]));
+
+            for ambient_occlusion in [false, true] {
+                let naive = build_mesh(&chunk, &neighbours, &registry, MeshingMode::Naive, ambient_occlusion);
+                let greedy = build_mesh(&chunk, &neighbours, &registry, MeshingMode::Greedy, ambient_occlusion);
+
+                let naive_faces = unit_faces_with_ao(&naive);
+                assert!(naive_faces.values().all(|(_, count)| *count == 1));
+                assert_eq!(naive_faces.len() as u32, naive.face_count());
+                assert_eq!(unit_faces_with_ao(&greedy), naive_faces);
+                assert_eq!(greedy.bounds, naive.bounds);
+                assert!(greedy.vertices.len() <= naive.vertices.len());
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_merges_flat_terrain() {
+        let registry = registry();
+        let chunk = Chunk::new(glm::vec3(0, 0, 0), |global_pos| if global_pos.y < 10 { BlockId(1) } else { BlockId::AIR });
+
+        let naive = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+        let greedy = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Greedy, true);
+
+        // 20 * 20 on the top and bottom, 20 * 10 on every side
+        assert_eq!(naive.face_counts, [200, 200, 400, 400, 200, 200]);
+        assert_eq!(naive.vertices.len(), 1600 * 4);
+        assert_eq!(naive.index_count(), 1600 * 6);
+        assert_eq!(greedy.face_counts, [1; 6]);
+        assert_eq!(greedy.vertices.len(), 6 * 4);
+        assert_eq!(unit_faces(&greedy), unit_faces(&naive));
+    }
+
+    #[test]
+    fn quad_indices_make_two_triangles_per_quad() {
+        assert!(quad_indices(0).is_empty());
+        assert_eq!(quad_indices(2), [0, 2, 3, 0, 1, 2, 4, 6, 7, 4, 5, 6]);
+
+        // the biggest index still has to point at a vertex of the last quad
+        let indices = quad_indices(1000);
+        assert_eq!(indices.len(), 6000);
+        assert_eq!(indices.iter().max(), Some(&3999));
+    }
+
+    #[test]
+    fn greedy_doesnt_merge_different_textures() {
+        let registry = registry();
+        // stone and grass in stripes along x, the tops cant merge across the stripes
+        let chunk = Chunk::new(glm::vec3(0, 0, 0), |global_pos| if global_pos.y == 0 { BlockId(1 + (global_pos.x % 2) as u16) } else { BlockId::AIR });
+
+        let greedy = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Greedy, true);
+
+        assert_eq!(greedy.face_counts[2], 20);
+        assert_eq!(unit_faces(&greedy), unit_faces(&build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true)));
+    }
+
+    /// air everywhere except for stone at `positions`
+    fn chunk_with(position: glm::IVec3, positions: &[LocalPos]) -> Chunk {
+        let mut chunk = Chunk::new(position, |_| BlockId::AIR);
+        for local_pos in positions {
+            chunk.set_block(*local_pos, BlockId(1));
+        }
+
+        chunk
+    }
+
+    #[test]
+    fn lone_block() {
+        let registry = registry();
+        let chunk = chunk_with(glm::vec3(0, 0, 0), &[glm::vec3(5, 6, 7)]);
+
+        for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, mode, true);
+
+            assert_eq!(mesh.face_counts, [1; 6]);
+            assert_eq!(mesh.vertices.len(), 24);
+            // the mesh goes from z - 1 to z
+            assert_eq!(mesh.bounds, Some((glm::vec3(5.0, 6.0, 6.0), glm::vec3(6.0, 7.0, 7.0))));
+
+            let faces = unit_faces(&mesh);
+            assert_eq!(faces, HashMap::from([
+                (([10, 13, 13], [-1, 0, 0], 3), 1),
+                (([12, 13, 13], [1, 0, 0], 3), 1),
+                (([11, 14, 13], [0, 1, 0], 3), 1),
+                (([11, 12, 13], [0, -1, 0], 3), 1),
+                (([11, 13, 12], [0, 0, -1], 3), 1),
+                (([11, 13, 14], [0, 0, 1], 3), 1)
+            ]));
+
+            // the top face, going bottom left, bottom right, top right, top left
+            let top = mesh.vertices[8..12].iter().map(|v| (v.position(), v.uv(), v.tile())).collect::<Vec<_>>();
+            assert_eq!(top, [
+                (glm::vec3(5.0, 7.0, 7.0), glm::vec2(0.0, 0.0), 3),
+                (glm::vec3(6.0, 7.0, 7.0), glm::vec2(1.0, 0.0), 3),
+                (glm::vec3(6.0, 7.0, 6.0), glm::vec2(1.0, 1.0), 3),
+                (glm::vec3(5.0, 7.0, 6.0), glm::vec2(0.0, 1.0), 3)
+            ]);
+        }
+    }
+
+    #[test]
+    fn adjacent_blocks_hide_the_faces_between_them() {
+        let registry = registry();
+        let chunk = chunk_with(glm::vec3(0, 0, 0), &[glm::vec3(5, 6, 7), glm::vec3(6, 6, 7)]);
+
+        let naive = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+        assert_eq!(naive.face_counts, [1, 1, 2, 2, 2, 2]);
+        assert_eq!(naive.bounds, Some((glm::vec3(5.0, 6.0, 6.0), glm::vec3(7.0, 7.0, 7.0))));
+        let faces = unit_faces(&naive);
+        assert_eq!(faces.len(), 10);
+        assert!(!faces.contains_key(&([12, 13, 13], [1, 0, 0], 3)));
+        assert!(!faces.contains_key(&([12, 13, 13], [-1, 0, 0], 3)));
+
+        let greedy = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Greedy, true);
+        assert_eq!(greedy.face_counts, [1; 6]);
+        assert_eq!(greedy.bounds, naive.bounds);
+        assert_eq!(unit_faces(&greedy), faces);
+    }
+
+    #[test]
+    fn faces_on_chunk_borders() {
+        let registry = registry();
+        let last = Chunk::SIZE as i8 - 1;
+
+        for (i, face) in Face::ALL.iter().enumerate() {
+            // a block right against the side of the chunk that face points to
+            let normal = face.normal();
+            let local_pos = glm::vec3(5, 6, 7).zip_map(&normal, |v, n| match n { -1 => 0, 1 => last, _ => v });
+            let chunk = chunk_with(glm::vec3(0, 0, 0), &[local_pos]);
+
+            let neighbour_position = glm::vec3(normal.x as i32, normal.y as i32, normal.z as i32) * Chunk::SIZE as i32;
+            let across = (local_pos + normal).map(|v| v.rem_euclid(Chunk::SIZE as i8));
+            let covering = chunk_with(neighbour_position, &[across]);
+            let air = chunk_with(neighbour_position, &[]);
+            // solid, but not where it would cover the face
+            let elsewhere = chunk_with(neighbour_position, &[across.map(|v| (v + 1) % Chunk::SIZE as i8)]);
+
+            let direction = glm::vec3(normal.x as i32, normal.y as i32, normal.z as i32);
+            for (name, neighbour, visible) in [("missing", None, true), ("air", Some(&air), true), ("elsewhere", Some(&elsewhere), true), ("covering", Some(&covering), false)] {
+                let mut neighbours = [None; 27];
+                neighbours[neighbour_index(direction)] = neighbour;
+
+                for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+                    let mesh = build_mesh(&chunk, &neighbours, &registry, mode, true);
+
+                    let mut expected = [1; 6];
+                    expected[i] = visible as u32;
+                    assert_eq!(mesh.face_counts, expected, "{face:?} with a {name} neighbour, {mode:?}");
+                }
+            }
+
+            // neighbours on the other sides dont change anything on this side
+            let mut neighbours = [Some(&covering); 27];
+            neighbours[neighbour_index(direction)] = None;
+            assert_eq!(build_mesh(&chunk, &neighbours, &registry, MeshingMode::Naive, true).face_counts, [1; 6]);
+        }
+    }
+
+    #[test]
+    fn full_chunk_bounds() {
+        let registry = registry();
+        let chunk = Chunk::new(glm::vec3(20, -40, 0), |_| BlockId(1));
+        let size = Chunk::SIZE as f32;
+
+        let mesh = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+        assert_eq!(mesh.face_counts, [400; 6]);
+        // positions are relative to the chunk
+        assert_eq!(mesh.bounds, Some((glm::vec3(0.0, 0.0, -1.0), glm::vec3(size, size, size - 1.0))));
+
+        let covered = Chunk::new(glm::vec3(0, 0, 0), |_| BlockId(1));
+        let mesh = build_mesh(&chunk, &[Some(&covered); 27], &registry, MeshingMode::Greedy, true);
+        assert!(mesh.is_empty());
+        assert_eq!(mesh.bounds, None);
+        assert_eq!(mesh.face_count(), 0);
+    }
+
+    /// the top face of the block at `local_pos`, in the order of `Face::corners`
+    fn top_face(mesh: &ChunkMesh, local_pos: LocalPos) -> [ChunkVertex; 4] {
+        let corner = glm::vec3(local_pos.x as f32, local_pos.y as f32 + 1.0, local_pos.z as f32);
+        quads(mesh).into_iter().find(|quad| quad[0].face() == 2 && quad[0].position() == corner).unwrap()
+    }
+
+    #[test]
+    fn ao_from_the_blocks_around_a_corner() {
+        let registry = registry();
+        let block = glm::vec3(5, 5, 5);
+        // the top corners go (-x, +z), (+x, +z), (+x, -z), (-x, -z)
+        for (name, occluders, ao) in [
+            ("nothing", vec![], [3, 3, 3, 3]),
+            ("edge", vec![glm::vec3(4, 6, 5)], [2, 3, 3, 2]),
+            ("diagonal", vec![glm::vec3(6, 6, 4)], [3, 3, 2, 3]),
+            ("edge and diagonal", vec![glm::vec3(4, 6, 5), glm::vec3(4, 6, 6)], [1, 3, 3, 2]),
+            // the diagonal doesnt matter once both edges are blocked
+            ("both edges", vec![glm::vec3(4, 6, 5), glm::vec3(5, 6, 6)], [0, 2, 3, 2]),
+            ("all three", vec![glm::vec3(4, 6, 5), glm::vec3(5, 6, 6), glm::vec3(4, 6, 6)], [0, 2, 3, 2]),
+            // blocks that arent in front of the face dont count
+            ("below", vec![glm::vec3(4, 5, 5), glm::vec3(4, 4, 6)], [3, 3, 3, 3])
+        ] {
+            let mut positions = occluders.clone();
+            positions.push(block);
+            let chunk = chunk_with(glm::vec3(0, 0, 0), &positions);
+
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+            assert_eq!(top_face(&mesh, block).map(|v| v.ao()), ao, "{name}");
+
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, false);
+            assert_eq!(top_face(&mesh, block).map(|v| v.ao()), [3; 4], "{name} without ao");
+        }
+    }
+
+    #[test]
+    fn quads_split_along_the_darker_diagonal() {
+        let registry = registry();
+        let block = glm::vec3(5, 5, 5);
+
+        // (+x, +z) is dark, so the split goes through the corner 1 and 3 instead of 0 and 2
+        for (occluder, first_corner) in [(glm::vec3(6, 6, 6), 1), (glm::vec3(4, 6, 6), 0)] {
+            let chunk = chunk_with(glm::vec3(0, 0, 0), &[block, occluder]);
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+
+            let corners = top_face(&mesh, block);
+            let top = mesh.vertices.chunks(4).find(|quad| quad.contains(&corners[0])).unwrap();
+            assert_eq!(top[0], corners[first_corner]);
+
+            // both triangles have the two corners of the diagonal, and the dark corner is one of them
+            let triangles = quad_indices(1).chunks(3).map(|triangle| triangle.iter().map(|i| top[*i as usize]).collect::<Vec<_>>()).collect::<Vec<_>>();
+            let shared = triangles[0].iter().filter(|v| triangles[1].contains(v)).collect::<Vec<_>>();
+            assert_eq!(shared.len(), 2);
+            assert!(shared.iter().any(|v| v.ao() < ChunkVertex::MAX_AO));
+        }
+    }
+
+    #[test]
+    fn ao_across_chunk_borders() {
+        let registry = registry();
+        let last = Chunk::SIZE as i8 - 1;
+        // in the corner of the chunk so the diagonal of the (-x, -z) corner is in the chunk diagonal to this one
+        let block = glm::vec3(0, 5, 0);
+        let chunk = chunk_with(glm::vec3(0, 0, 0), &[block]);
+
+        let west = chunk_with(glm::vec3(-20, 0, 0), &[glm::vec3(last, 6, 0)]);
+        let diagonal = chunk_with(glm::vec3(-20, 0, -20), &[glm::vec3(last, 6, last)]);
+
+        for (name, neighbours, ao) in [
+            ("missing", neighbours(&[]), [3, 3, 3, 3]),
+            ("west", neighbours(&[(glm::vec3(-1, 0, 0), &west)]), [2, 3, 3, 2]),
+            ("diagonal", neighbours(&[(glm::vec3(-1, 0, -1), &diagonal)]), [3, 3, 3, 2]),
+            ("both", neighbours(&[(glm::vec3(-1, 0, 0), &west), (glm::vec3(-1, 0, -1), &diagonal)]), [2, 3, 3, 1])
+        ] {
+            for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+                let mesh = build_mesh(&chunk, &neighbours, &registry, mode, true);
+                assert_eq!(top_face(&mesh, block).map(|v| v.ao()), ao, "{name}, {mode:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn greedy_doesnt_merge_different_ao() {
+        let registry = registry();
+        // flat ground with one block on it, the ground around the block is darker
+        let mut positions = (0..Chunk::SIZE as i8).flat_map(|x| (0..Chunk::SIZE as i8).map(move |z| glm::vec3(x, 0, z))).collect::<Vec<_>>();
+        positions.push(glm::vec3(10, 1, 10));
+        let chunk = chunk_with(glm::vec3(0, 0, 0), &positions);
+
+        let without_ao = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Greedy, false);
+        let with_ao = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Greedy, true);
+
+        assert!(with_ao.face_counts[2] > without_ao.face_counts[2]);
+        assert_eq!(unit_faces(&with_ao), unit_faces(&without_ao));
+        assert!(without_ao.vertices.iter().all(|v| v.ao() == ChunkVertex::MAX_AO));
+
+        // the 8 faces of the ground around the block and the bottom of the sides of the block, nothing else
+        let darker = unit_faces_with_ao(&with_ao).into_iter().filter(|(_, (ao, _))| *ao != [ChunkVertex::MAX_AO; 4]).count();
+        assert_eq!(darker, 8 + 4);
+    }
+
+    #[test]
+    fn faces_get_the_light_of_the_block_in_front() {
+        let registry = registry();
+        let last = Chunk::SIZE as i8 - 1;
+        // two blocks next to each other that would be one quad without the light
+        let mut chunk = chunk_with(glm::vec3(0, 0, 0), &[glm::vec3(5, 5, 5), glm::vec3(6, 5, 5), glm::vec3(5, last, 5)]);
+        chunk.set_light(glm::vec3(5, 6, 5), LightChannel::Sky, 7);
+        chunk.set_light(glm::vec3(5, 6, 5), LightChannel::Red, 3);
+        chunk.set_light(glm::vec3(6, 6, 5), LightChannel::Green, 12);
+        chunk.set_light(glm::vec3(6, 6, 5), LightChannel::Blue, 4);
+
+        let mut above = chunk_with(glm::vec3(0, 20, 0), &[]);
+        above.set_light(glm::vec3(5, 0, 5), LightChannel::Sky, 9);
+
+        for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+            let mesh = build_mesh(&chunk, &neighbours(&[(glm::vec3(0, 1, 0), &above)]), &registry, mode, false);
+            assert_eq!(top_face(&mesh, glm::vec3(5, 5, 5)).map(|v| (v.sky_light(), v.block_light())), [(7, [3, 0, 0]); 4], "{mode:?}");
+            assert_eq!(top_face(&mesh, glm::vec3(6, 5, 5)).map(|v| (v.sky_light(), v.block_light())), [(0, [0, 12, 4]); 4], "{mode:?}");
+            assert_eq!(top_face(&mesh, glm::vec3(5, last, 5)).map(|v| (v.sky_light(), v.block_light())), [(9, [0; 3]); 4], "{mode:?}");
+
+            // without the chunk above theres nothing to say its dark up there
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, mode, false);
+            assert_eq!(top_face(&mesh, glm::vec3(5, last, 5)).map(|v| (v.sky_light(), v.block_light())), [(MAX_LIGHT, [0; 3]); 4], "{mode:?}");
+        }
+    }
+
+    /// the translucent part of `mesh` in place of the rest, so the helpers above work on it
+    fn translucent_part(mesh: &ChunkMesh) -> ChunkMesh {
+        ChunkMesh {
+            vertices: mesh.translucent.clone(),
+            ..mesh.clone()
+        }
+    }
+
+    #[test]
+    fn see_through_blocks_only_hide_the_same_block() {
+        let registry = registry();
+        let (stone, glass, leaves) = (BlockId(1), BlockId(3), BlockId(4));
+        let chunk = Chunk::new(glm::vec3(0, 0, 0), |pos| match (pos.x, pos.y, pos.z) {
+            (5 | 6, 5, 5) => glass,
+            (5, 6, 5) => stone,
+            (7 | 8, 5, 5) => leaves,
+            _ => BlockId::AIR
+        });
+
+        for mode in [MeshingMode::Naive, MeshingMode::Greedy] {
+            let mesh = build_mesh(&chunk, &[None; 27], &registry, mode, true);
+            let opaque = unit_faces(&mesh);
+            let translucent = unit_faces(&translucent_part(&mesh));
+
+            // the two glass blocks dont have faces between them and the stone hides the top of one,
+            // but the leaves next to them are a different block
+            assert_eq!(translucent.values().sum::<u32>(), 10 - 1, "{mode:?}");
+            assert!(translucent.keys().all(|(_, _, tile)| *tile == 4), "{mode:?}");
+            assert!(translucent.contains_key(&([14, 11, 9], [1, 0, 0], 4)), "{mode:?}");
+            assert!(!translucent.contains_key(&([12, 11, 9], [1, 0, 0], 4)), "{mode:?}");
+
+            // the stone and the leaves dont get hidden by the glass, the leaves still hide each other
+            assert_eq!(opaque.values().sum::<u32>(), 6 + 10, "{mode:?}");
+            assert!(opaque.contains_key(&([11, 12, 9], [0, -1, 0], 3)), "{mode:?}");
+            assert!(opaque.contains_key(&([14, 11, 9], [-1, 0, 0], 5)), "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn translucent_quads_get_sorted_back_to_front() {
+        let registry = registry();
+        let chunk = Chunk::new(glm::vec3(0, 0, 0), |pos| if [2, 10, 17].contains(&pos.x) && pos.y == 5 && pos.z == 5 { BlockId(3) } else { BlockId::AIR });
+        let mut mesh = build_mesh(&chunk, &[None; 27], &registry, MeshingMode::Naive, true);
+        assert_eq!(mesh.translucent.len(), 3 * 6 * VERTICES_PER_QUAD as usize);
+
+        let eye = glm::vec3(0.0, 5.5, 4.5);
+        sort_back_to_front(&mut mesh.translucent, eye);
+
+        let distances = quads(&translucent_part(&mesh)).iter().map(|quad| glm::distance(&((quad[0].position() + quad[2].position()) / 2.0), &eye)).collect::<Vec<_>>();
+        assert!(distances.windows(2).all(|pair| pair[0] >= pair[1]), "{distances:?}");
+        // the far side of the block furthest away goes first and the near side of the closest one last
+        assert_eq!(quads(&translucent_part(&mesh))[0][0].position().x, 18.0);
+        assert_eq!(quads(&translucent_part(&mesh)).last().unwrap()[0].position().x, 2.0);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::json::JsonValue;
+
+/// dev names in the order blocks.json had them before worlds saved their own block ids,
+/// chunks from back then used these as their ids
+pub const LEGACY_BLOCK_IDS: [&str; 15] = [
+    "air", "grass_block", "dirt", "stone", "sand", "snow", "coal_ore", "iron_ore", "gold_ore", "diamond_ore",
+    "oak_log", "oak_leaves", "cobblestone", "dandelion", "poppy"
+];
+
+/// upgrades one version to the next, `LEVEL_MIGRATIONS[0]` goes from 1 to 2 and so on
+type LevelMigration = fn(&mut JsonValue) -> Result<(), String>;
+type ChunkMigration = fn(RawChunk) -> Result<RawChunk, String>;
+
+const LEVEL_MIGRATIONS: [LevelMigration; 2] = [level_v1_to_v2, level_v2_to_v3];
+const CHUNK_MIGRATIONS: [ChunkMigration; 1] = [chunk_v1_to_v2];
+
+pub const LEVEL_VERSION: u32 = LEVEL_MIGRATIONS.len() as u32 + 1;
+pub const CHUNK_VERSION: u16 = CHUNK_MIGRATIONS.len() as u16 + 1;
+
+/// a chunk payload after decompressing, the ids are saved ids (see `BlockIdMap`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawChunk {
+    pub palette: Vec<u16>,
+    pub bits: u32,
+    pub data: Vec<u64>
+}
+
+/// brings a level file from whatever version its at up to `LEVEL_VERSION`
+pub fn migrate_level(json: &mut JsonValue) -> Result<(), String> {
+    let version = json.get("version").and_then(|v| v.as_f64()).ok_or_else(|| "missing \"version\"".to_string())?;
+    if version.fract() != 0.0 || version < 1.0 || version > LEVEL_VERSION as f64 {
+        return Err(format!("unsupported level version {version}, newest is {LEVEL_VERSION}"));
+    }
+
+    for (from, migration) in LEVEL_MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        migration(json).map_err(|e| format!("couldn't upgrade level from version {}: {e}", from + 1))?;
+        json.set("version", JsonValue::Number(from as f64 + 2.0));
+    }
+
+    Ok(())
+}
+
+/// brings a chunk from `version` up to `CHUNK_VERSION`
+pub fn migrate_chunk(mut chunk: RawChunk, version: u16) -> Result<RawChunk, String> {
+    if !(1..=CHUNK_VERSION).contains(&version) {
+        return Err(format!("unsupported chunk version {version}, newest is {CHUNK_VERSION}"));
+    }
+
+    for (from, migration) in CHUNK_MIGRATIONS.iter().enumerate().skip(version as usize - 1) {
+        chunk = migration(chunk).map_err(|e| format!("couldn't upgrade chunk from version {}: {e}", from + 1))?;
+    }
+
+    Ok(chunk)
+}
+
+/// version 2 added the block ids the world was saved with
+fn level_v1_to_v2(json: &mut JsonValue) -> Result<(), String> {
+    json.set("block_ids", JsonValue::Array(LEGACY_BLOCK_IDS.iter().map(|name| JsonValue::String(name.to_string())).collect()));
+
+    Ok(())
+}
+
+/// version 3 added the save settings, these are the defaults from back then
+fn level_v2_to_v3(json: &mut JsonValue) -> Result<(), String> {
+    json.set("save", JsonValue::Object(vec![
+        ("autosave_interval".to_string(), JsonValue::Number(60.0)),
+        ("save_on_exit".to_string(), JsonValue::Bool(true)),
+        ("save_on_panic".to_string(), JsonValue::Bool(true))
+    ]));
+
+    Ok(())
+}
+
+/// version 2 only started writing the version into every chunk, the data itself is the same
+/// since the legacy ids are exactly the first saved ids of a migrated world
+fn chunk_v1_to_v2(chunk: RawChunk) -> Result<RawChunk, String> {
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{json::JsonValue, world::{level::LevelData, save::SaveSettings}};
+    use super::{migrate_chunk, migrate_level, RawChunk, CHUNK_VERSION, LEGACY_BLOCK_IDS, LEVEL_VERSION};
+
+    const LEVEL_V1: &str = include_str!("../../tests/fixtures/level_v1.json");
+
+    #[test]
+    fn migrates_v1_levels() {
+        let original = JsonValue::parse(LEVEL_V1).unwrap();
+        assert_eq!(original.get("block_ids"), None);
+
+        let mut json = original.clone();
+        migrate_level(&mut json).unwrap();
+        assert_eq!(json.get("version"), Some(&JsonValue::Number(LEVEL_VERSION as f64)));
+        assert_eq!(json.get("block_ids").unwrap().as_array().unwrap().iter().map(|id| id.as_str().unwrap()).collect::<Vec<_>>(), LEGACY_BLOCK_IDS);
+
+        // migrating again doesnt change anything
+        let migrated = json.clone();
+        migrate_level(&mut json).unwrap();
+        assert_eq!(json, migrated);
+
+        let level = LevelData::from_json(&original).unwrap();
+        assert_eq!(level.config.seed, 987654);
+        assert_eq!(level.game_time, 1234.5);
+        assert_eq!(level.player_position, glm::vec3(21.25, -7.5, 3.0));
+        assert_eq!(level.block_ids, LEGACY_BLOCK_IDS);
+        assert_eq!(level.save_settings, SaveSettings::default());
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        for version in ["0", "1.5", "-1", &(LEVEL_VERSION + 1).to_string(), "\"1\""] {
+            let mut json = JsonValue::parse(&LEVEL_V1.replacen("\"version\": 1", &format!("\"version\": {version}"), 1)).unwrap();
+            assert!(migrate_level(&mut json).is_err(), "version {version}");
+        }
+
+        let mut json = JsonValue::parse("{}").unwrap();
+        assert!(migrate_level(&mut json).is_err());
+
+        let chunk = RawChunk { palette: vec![0], bits: 0, data: Vec::new() };
+        assert_eq!(migrate_chunk(chunk.clone(), 1), Ok(chunk.clone()));
+        assert!(migrate_chunk(chunk.clone(), 0).is_err());
+        assert!(migrate_chunk(chunk, CHUNK_VERSION + 1).is_err());
+    }
+}
@@ -1,160 +1,561 @@
 pub mod chunk;
 pub mod block;
+pub mod palette;
+pub mod registry;
+pub mod definitions;
+pub mod config;
+pub mod level;
+pub mod generation;
+pub mod raycast;
+pub mod region;
+pub mod id_map;
+pub mod migration;
+pub mod save;
+pub mod mesher;
+pub mod workers;
+pub mod light;
 
-use std::{collections::HashMap, mem::size_of};
+use std::{collections::{HashMap, HashSet}, mem::size_of, sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}}};
 use ash::vk;
-use noise::{Perlin, NoiseFn};
-use crate::{timer::Timer, engine::{buffer::Buffer, vertex::Vertex, self}};
-use self::{chunk::Chunk, block::{BlockType, Block}};
+use crate::engine::{buffer::Buffer, vertex::ChunkVertex, self};
+use self::{chunk::{Chunk, ChunkResources, GlobalPos, BufferOffset}, block::{BlockId, BlockType}, registry::BlockRegistry, raycast::RaycastHit, region::RegionStorage, mesher::{ChunkMesh, MeshingMode}, workers::{ChunkWorkers, Job, JobResult, WorkerContext}, generation::{TerrainGenerator, biome::Biome, decoration::{self, PendingBlocks}}, light::LightUpdate};
 
 /// has position of 1, 2, 3 instead of going in intervals of `Chunk::SIZE`
-type ChunkPos = glm::I8Vec3;
+type ChunkPos = glm::IVec3;
 
+/// chunks get loaded and meshed on worker threads, the main thread only decorates them,
+/// keeps track of what needs meshing and uploads the finished meshes
 pub struct World {
     chunks: HashMap<ChunkPos, Chunk>,
-    world_vertex_buffer: Buffer<Vertex>,
-    half_distance: i32
+    /// registry, generator and storage, shared with the workers
+    context: Arc<WorkerContext>,
+    workers: ChunkWorkers,
+    world_vertex_buffer: Buffer<ChunkVertex>,
+    /// `mesher::quad_indices` for the biggest mesh a chunk can have, every chunk draws with it
+    quad_index_buffer: Buffer<u32>,
+    half_distance: i32,
+    /// the chunk the player is in, chunks are loaded in a cube around it
+    center: ChunkPos,
+    /// where the player is in block space, translucent quads get sorted back to front from here
+    eye: glm::Vec3,
+    /// the block `eye` was in when the translucent quads were last sorted, they only get sorted
+    /// again when it moves into another block
+    sorted_from: GlobalPos,
+    /// where each loaded chunk keeps its vertices in `world_vertex_buffer`
+    buffer_offsets: HashMap<ChunkPos, BufferOffset>,
+    /// parts of `world_vertex_buffer` that no loaded chunk is using
+    free_buffer_offsets: Vec<BufferOffset>,
+    /// gpu resources of chunks that were unloaded this frame, the frame thats still
+    /// on the gpu could be drawing them so theyre only freed in the next `update_world`
+    retired_resources: Vec<ChunkResources>,
+    /// chunks a worker is loading, setting the flag makes it skip the chunk
+    loading: HashMap<ChunkPos, Arc<AtomicBool>>,
+    /// version of the newest mesh job of every chunk, older meshes that come back get thrown away
+    mesh_versions: HashMap<ChunkPos, u64>,
+    next_mesh_version: u64,
+    /// meshes waiting for their turn to be uploaded
+    finished_meshes: HashMap<ChunkPos, ChunkMesh>,
+    meshing_mode: MeshingMode,
+    ambient_occlusion: bool,
+    /// parts of features that stick out into chunks which arent loaded
+    pending_blocks: PendingBlocks,
+    /// positions of the chunks that changed since they were last meshed
+    dirty_chunks: HashSet<GlobalPos>,
+    /// loaded chunks that were edited since they were last saved, or that generating again
+    /// wouldnt give back (see `decorate_chunk`)
+    modified_chunks: HashSet<GlobalPos>
 }
 
 impl World {
-    pub const VERTICES_PER_BLOCK: u64 = 36;
-    pub const MAX_VERTICES_PER_CHUNK_BYTES: u64 = ((((Chunk::SIZE as u64).pow(3) / 2) + Chunk::SIZE as u64) * World::VERTICES_PER_BLOCK) * size_of::<Vertex>() as u64;
+    pub const QUADS_PER_BLOCK: u64 = 6;
+    /// worst case of the naive mesher, greedy meshes are never bigger than naive ones
+    pub const MAX_QUADS_PER_CHUNK: u64 = (((Chunk::SIZE as u64).pow(3) / 2) + Chunk::SIZE as u64) * World::QUADS_PER_BLOCK;
+    pub const MAX_VERTICES_PER_CHUNK_BYTES: u64 = World::MAX_QUADS_PER_CHUNK * mesher::VERTICES_PER_QUAD * size_of::<ChunkVertex>() as u64;
+    /// how many jobs can wait for a worker at once
+    pub const JOB_QUEUE_SIZE: usize = 64;
+    /// how many finished meshes get copied to the gpu each frame at most, the closest ones go first
+    pub const MESH_UPLOADS_PER_FRAME: usize = 8;
 
-    pub fn new(distance: u32) -> World {
+    /// doesnt load anything itself, the chunks around `player_position` (camera space) come in over the next frames
+    pub fn new(distance: u32, registry: BlockRegistry, generator: impl TerrainGenerator + 'static, seed: u32, storage: RegionStorage, player_position: glm::Vec3) -> World {
+        let chunk_count = (distance as u64).pow(3);
         // max vertices per chunk in bytes with some padding
-        let vertex_buffer = Buffer::new_empty(World::MAX_VERTICES_PER_CHUNK_BYTES * (distance as u64).pow(3), vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
-
-        let mut chunks = HashMap::with_capacity((Chunk::SIZE as usize).pow(3));
-
-        let half_distance = distance as i32 / 2;
-
-        let perlin = Perlin::new(123);
-
-        let mut generating_terrain_timer = Timer::new();
-        for x in -half_distance..half_distance {
-            for y in -half_distance..half_distance {
-                for z in -half_distance..half_distance {
-                    chunks.insert(glm::vec3(x as i8, y as i8, z as i8), Chunk::new(glm::vec3(x as i32 * Chunk::SIZE as i32, y as i32 * Chunk::SIZE as i32, z as i32 * Chunk::SIZE as i32), |global_pos| {
-                        let perlin_y = perlin.get([global_pos.x as f64 / 200_000_000.0, global_pos.z as f64 / 200_000_000.0]) * 200_000_000.0;
-                        let perlin_y = (perlin_y as i32).div_euclid(10);
-                        
-                        if global_pos.y < perlin_y {
-                            Block::new("Grass Block", "grass_block", BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.1, 0.0), glm::vec2(0.2, 0.0))
-                        } else {
-                            Block::new("Air", "air", BlockType::Air, glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9))
-                        }
-                    }));
-                }
-            }
+        let vertex_buffer = Buffer::new_empty(World::MAX_VERTICES_PER_CHUNK_BYTES * chunk_count, vk::BufferUsageFlags::VERTEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT);
+        let quad_index_buffer = Buffer::new(&mesher::quad_indices(World::MAX_QUADS_PER_CHUNK), vk::BufferUsageFlags::INDEX_BUFFER, vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT).unwrap();
+
+        let context = Arc::new(WorkerContext {
+            registry,
+            generator: Box::new(generator),
+            seed,
+            storage: Mutex::new(storage)
+        });
+
+        let mut world = World {
+            chunks: HashMap::with_capacity(chunk_count as usize),
+            workers: ChunkWorkers::new(ChunkWorkers::default_thread_count(), World::JOB_QUEUE_SIZE, context.clone()),
+            context,
+            world_vertex_buffer: vertex_buffer,
+            quad_index_buffer,
+            half_distance: distance as i32 / 2,
+            center: World::player_chunk(player_position),
+            eye: raycast::to_block_space(player_position),
+            sorted_from: World::block_of(raycast::to_block_space(player_position)),
+            buffer_offsets: HashMap::with_capacity(chunk_count as usize),
+            free_buffer_offsets: (0..chunk_count).rev().map(|i| i * World::MAX_VERTICES_PER_CHUNK_BYTES).collect(),
+            retired_resources: Vec::new(),
+            loading: HashMap::new(),
+            mesh_versions: HashMap::new(),
+            next_mesh_version: 0,
+            finished_meshes: HashMap::new(),
+            meshing_mode: MeshingMode::default(),
+            ambient_occlusion: true,
+            pending_blocks: PendingBlocks::new(),
+            dirty_chunks: HashSet::new(),
+            modified_chunks: HashSet::new()
+        };
+
+        world.load_chunks();
+
+        world
+    }
+
+    /// unloads the chunks that left the range, takes in whatever the workers finished,
+    /// gives them new jobs and uploads some of the finished meshes, should be called once a frame
+    pub fn update_world(&mut self, player_position: glm::Vec3) {
+        self.center = World::player_chunk(player_position);
+        self.eye = raycast::to_block_space(player_position);
+
+        // `render_surface` waited for the frame that could still use these since they were retired
+        self.retired_resources.clear();
+
+        let leaving = self.chunks.keys().filter(|key| !self.in_range(**key)).copied().collect::<Vec<_>>();
+        let mut unloaded = Vec::with_capacity(leaving.len());
+        for key in leaving {
+            let mut chunk = self.chunks.remove(&key).unwrap();
+            self.free_buffer_offsets.push(self.buffer_offsets.remove(&key).unwrap());
+            self.retired_resources.extend(chunk.take_resources());
+            self.mesh_versions.remove(&key);
+            self.finished_meshes.remove(&key);
+            unloaded.push(chunk);
         }
-        generating_terrain_timer.tick();
-        println!("Generated terrain in {}s", generating_terrain_timer.elapsed());
-        generating_terrain_timer.reset();
+        self.unload_chunks(&unloaded);
+
+        let center = self.center;
+        let half_distance = self.half_distance;
+        self.loading.retain(|key, cancelled| {
+            let keep = World::in_range_of(center, half_distance, *key);
+            if !keep {
+                cancelled.store(true, Ordering::Relaxed);
+            }
+            keep
+        });
 
-        let mut offset = 0;
+        self.receive_results();
+        self.load_chunks();
+        self.mesh_dirty_chunks();
+        self.upload_meshes();
+        self.sort_translucent();
+    }
 
-        let mut generating_mesh_timer = Timer::new();
-        for x in -half_distance..half_distance {
-            for y in -half_distance..half_distance {
-                for z in -half_distance..half_distance {
-                    let x = x as i8;
-                    let y = y as i8;
-                    let z = z as i8;
+    /// the block a position in block space is in
+    fn block_of(position: glm::Vec3) -> GlobalPos {
+        glm::vec3(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32)
+    }
 
-                    let chunk = chunks.get(&glm::vec3(x, y, z)).unwrap() as *const Chunk;
+    /// the chunk `player_position` (camera space) is in
+    fn player_chunk(player_position: glm::Vec3) -> ChunkPos {
+        let block_position = raycast::to_block_space(player_position);
 
-                    let west_chunk = chunks.get(&glm::vec3(x - 1, y, z)).map(|c| c as *const Chunk);
-                    let east_chunk = chunks.get(&glm::vec3(x + 1, y, z)).map(|c| c as *const Chunk);
-                    let up_chunk = chunks.get(&glm::vec3(x, y + 1, z)).map(|c| c as *const Chunk);
-                    let down_chunk = chunks.get(&glm::vec3(x, y - 1, z)).map(|c| c as *const Chunk);
-                    let north_chunk = chunks.get(&glm::vec3(x, y, z - 1)).map(|c| c as *const Chunk);
-                    let south_chunk = chunks.get(&glm::vec3(x, y, z + 1)).map(|c| c as *const Chunk);
+        glm::vec3(
+            (block_position.x / Chunk::SIZE as f32).floor() as i32,
+            (block_position.y / Chunk::SIZE as f32).floor() as i32,
+            (block_position.z / Chunk::SIZE as f32).floor() as i32
+        )
+    }
 
-                    chunk::build_mesh(chunk, [west_chunk, east_chunk, up_chunk, down_chunk, north_chunk, south_chunk], &vertex_buffer, offset);
-                    offset += World::MAX_VERTICES_PER_CHUNK_BYTES;
+    fn in_range(&self, key: ChunkPos) -> bool {
+        World::in_range_of(self.center, self.half_distance, key)
+    }
+
+    fn in_range_of(center: ChunkPos, half_distance: i32, key: ChunkPos) -> bool {
+        let offset = key - center;
+        offset.iter().all(|o| *o >= -half_distance && *o < half_distance)
+    }
+
+    /// squared so it stays an integer, only used for sorting
+    fn distance_to_center(&self, key: ChunkPos) -> i32 {
+        (key - self.center).map(|o| o * o).sum()
+    }
+
+    /// gives the workers load jobs for the missing chunks in range, closest to the player first, until the queue is full
+    fn load_chunks(&mut self) {
+        let mut missing = Vec::new();
+        for x in -self.half_distance..self.half_distance {
+            for y in -self.half_distance..self.half_distance {
+                for z in -self.half_distance..self.half_distance {
+                    let key = self.center + glm::vec3(x, y, z);
+                    if !self.chunks.contains_key(&key) && !self.loading.contains_key(&key) {
+                        missing.push(key);
+                    }
                 }
             }
         }
-        generating_mesh_timer.tick();
-        println!("Generated mesh in {}s", generating_mesh_timer.elapsed());
-        generating_mesh_timer.reset();
+        missing.sort_by_key(|key| self.distance_to_center(*key));
 
-        World {
-            chunks,
-            world_vertex_buffer: vertex_buffer,
-            half_distance
+        for key in missing {
+            let cancelled = Arc::new(AtomicBool::new(false));
+            if !self.workers.submit(Job::Load { key, cancelled: cancelled.clone() }) {
+                break;
+            }
+            self.loading.insert(key, cancelled);
         }
     }
 
-    pub fn update_world(&mut self, player_position: glm::Vec3) {
-        let player_position = glm::vec3(
-            player_position.x,
-            player_position.y * -1.0,
-            player_position.z
-        );
-        let player_position = glm::vec3(
-            (player_position.x - (player_position.x % Chunk::SIZE as f32)) as i32,
-            (player_position.y - (player_position.y % Chunk::SIZE as f32)) as i32,
-            (player_position.z - (player_position.z % Chunk::SIZE as f32)) as i32
-        );
-        println!("Player position: {:?}", player_position);
-
-        let perlin = Perlin::new(123);
+    fn receive_results(&mut self) {
+        while let Some(result) = self.workers.receive() {
+            match result {
+                JobResult::Loaded { key, chunk, decorations } => {
+                    // it left the range while it was loading
+                    if self.loading.remove(&key).is_none() || self.chunks.contains_key(&key) {
+                        continue;
+                    }
 
-        for x in -self.half_distance..self.half_distance {
-            for y in -self.half_distance..self.half_distance {
-                for z in -self.half_distance..self.half_distance {
-                    let chunk = self.chunks.get(&glm::vec3(x as i8, y as i8, z as i8)).unwrap();
-
-                    if chunk.position() != player_position + glm::vec3(x as i32 * Chunk::SIZE as i32, y as i32 * Chunk::SIZE as i32, z as i32 * Chunk::SIZE as i32) {
-                        let old_chunk = self.chunks.insert(
-                            glm::vec3(x as i8, y as i8, z as i8),
-                            Chunk::new(
-                                player_position + glm::vec3(x as i32 * Chunk::SIZE as i32, y as i32 * Chunk::SIZE as i32, z as i32 * Chunk::SIZE as i32),
-                                |global_pos| {
-                                    let perlin_y = perlin.get([global_pos.x as f64 / 200_000_000.0, global_pos.z as f64 / 200_000_000.0]) * 200_000_000.0;
-                                    let perlin_y = (perlin_y as i32).div_euclid(10);
-                        
-                                    if global_pos.y < perlin_y {
-                                        Block::new("Grass Block", "grass_block", BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.1, 0.0), glm::vec2(0.2, 0.0))
-                                    } else {
-                                        Block::new("Air", "air", BlockType::Air, glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9))
-                                    }
-                                }
-                            )
-                        ).unwrap();
-
-                        let x = x as i8;
-                        let y = y as i8;
-                        let z = z as i8;
-
-                        let west_chunk = self.chunks.get(&glm::vec3(x - 1, y, z)).map(|c| c as *const Chunk);
-                        let east_chunk = self.chunks.get(&glm::vec3(x + 1, y, z)).map(|c| c as *const Chunk);
-                        let up_chunk = self.chunks.get(&glm::vec3(x, y + 1, z)).map(|c| c as *const Chunk);
-                        let down_chunk = self.chunks.get(&glm::vec3(x, y - 1, z)).map(|c| c as *const Chunk);
-                        let north_chunk = self.chunks.get(&glm::vec3(x, y, z - 1)).map(|c| c as *const Chunk);
-                        let south_chunk = self.chunks.get(&glm::vec3(x, y, z + 1)).map(|c| c as *const Chunk);
-
-                        chunk::build_mesh(
-                            self.chunks.get(&glm::vec3(x as i8, y as i8, z as i8)).unwrap(),
-                            [west_chunk, east_chunk, up_chunk, down_chunk, north_chunk, south_chunk],
-                            &self.world_vertex_buffer,
-                            old_chunk.buffer_offset(),
-
-                        )
+                    let chunk_position = chunk.position();
+                    self.chunks.insert(key, chunk);
+                    self.buffer_offsets.insert(key, self.free_buffer_offsets.pop().unwrap());
+
+                    let placed_elsewhere = self.decorate_chunk(key, decorations);
+                    self.update_light(|light| {
+                        light.light_chunk(key);
+                        light.update_blocks(&placed_elsewhere);
+                    });
+
+                    // the neighbours were drawing their faces on this side while it wasnt there,
+                    // and their ao near this side didnt know about its blocks
+                    self.dirty_chunks.insert(chunk_position);
+                    for direction in World::neighbour_directions() {
+                        self.dirty_chunks.insert(chunk_position + direction * Chunk::SIZE as i32);
+                    }
+                }
+                JobResult::Meshed { key, version, mesh } => {
+                    if self.mesh_versions.get(&key) == Some(&version) {
+                        self.finished_meshes.insert(key, mesh);
                     }
                 }
+                // the worker already printed where it happened
+                JobResult::Panicked(payload) => std::panic::resume_unwind(payload)
+            }
+        }
+    }
+
+    /// None if the chunk `global_pos` is in isnt loaded
+    pub fn get_block(&self, global_pos: GlobalPos) -> Option<BlockId> {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        let key = self.loaded_chunk_key(chunk_position)?;
+
+        self.chunks.get(&key).unwrap().get_block(local_pos)
+    }
+
+    /// gives back the block that was there before, or None if the chunk `global_pos` is in isnt loaded
+    ///
+    /// the chunk (and its neighbours if the block was on the border) gets remeshed in the next `update_world`
+    pub fn set_block(&mut self, global_pos: GlobalPos, block: BlockId) -> Option<BlockId> {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        let key = self.loaded_chunk_key(chunk_position)?;
+
+        let old_block = self.chunks.get_mut(&key).unwrap().set_block(local_pos, block).unwrap();
+        if old_block == block {
+            return Some(old_block);
+        }
+
+        self.modified_chunks.insert(chunk_position);
+
+        let (old, new) = (self.context.registry.get(old_block), self.context.registry.get(block));
+        let light_changed = old.emission() != new.emission() || old.opacity != new.opacity;
+        let (old_type, new_type) = (old.block_type, new.block_type);
+
+        if light_changed {
+            self.update_light(|light| light.update_blocks(&[global_pos]));
+        }
+        self.dirty_chunks.extend(World::remeshed_by_edit(global_pos, old_type, new_type));
+
+        Some(old_block)
+    }
+
+    /// finds the first block that isnt air along the ray, unloaded chunks count as air
+    ///
+    /// `origin` and `direction` are in the same space as the camera (like `Camera::position` and `Camera::direction`)
+    pub fn raycast(&self, origin: glm::Vec3, direction: glm::Vec3, max_distance: f32) -> Option<RaycastHit> {
+        let origin = raycast::to_block_space(origin);
+        // directions dont get shifted, only flipped
+        let direction = glm::vec3(direction.x, -direction.y, direction.z);
+
+        raycast::raycast(origin, direction, max_distance, |global_pos| {
+            self.get_block(global_pos).is_some_and(|block| self.context.registry.get(block).block_type != BlockType::Air)
+        })
+    }
+
+    /// gives the workers mesh jobs for the dirty chunks whose neighbours are all there,
+    /// so chunks dont get meshed again for every neighbour that comes in
+    ///
+    /// all the edits since the last frame only cause one remesh per chunk
+    fn mesh_dirty_chunks(&mut self) {
+        let mut dirty = std::mem::take(&mut self.dirty_chunks).into_iter().collect::<Vec<_>>();
+        dirty.sort_by_key(|chunk_position| self.distance_to_center(chunk_position / Chunk::SIZE as i32));
+
+        let mut queue_full = false;
+        for chunk_position in dirty {
+            // unloaded chunks get meshed when theyre loaded again
+            let Some(key) = self.loaded_chunk_key(chunk_position) else {
+                continue;
+            };
+
+            if queue_full || !self.neighbours_ready(key) {
+                self.dirty_chunks.insert(chunk_position);
+                continue;
+            }
+
+            let version = self.next_mesh_version;
+            let job = Job::Mesh {
+                key,
+                version,
+                mode: self.meshing_mode,
+                ambient_occlusion: self.ambient_occlusion,
+                chunk: Box::new(self.chunks.get(&key).unwrap().snapshot()),
+                neighbours: Box::new(std::array::from_fn(|i| {
+                    let direction = mesher::neighbour_direction(i);
+                    (direction != glm::vec3(0, 0, 0)).then(|| self.chunks.get(&(key + direction)).map(|c| c.snapshot())).flatten()
+                }))
+            };
+
+            if self.workers.submit(job) {
+                self.next_mesh_version += 1;
+                self.mesh_versions.insert(key, version);
+            } else {
+                queue_full = true;
+                self.dirty_chunks.insert(chunk_position);
             }
         }
     }
 
+    /// neighbours that are out of range never come, so theyre ready too
+    fn neighbours_ready(&self, key: ChunkPos) -> bool {
+        World::neighbour_directions().all(|direction| {
+            let neighbour = key + direction;
+            self.chunks.contains_key(&neighbour) || !self.in_range(neighbour)
+        })
+    }
+
+    fn upload_meshes(&mut self) {
+        let mut keys = self.finished_meshes.keys().copied().collect::<Vec<_>>();
+        keys.sort_by_key(|key| self.distance_to_center(*key));
+
+        for key in keys.into_iter().take(World::MESH_UPLOADS_PER_FRAME) {
+            let mesh = self.finished_meshes.remove(&key).unwrap();
+            let offset = *self.buffer_offsets.get(&key).unwrap();
+
+            self.chunks.get_mut(&key).unwrap().upload_mesh(&mesh, &self.world_vertex_buffer, offset, self.eye);
+        }
+    }
+
+    /// new meshes are already sorted for where the player is when theyre uploaded, the others only
+    /// have to be sorted again once the player moves into another block
+    fn sort_translucent(&mut self) {
+        let eye_block = World::block_of(self.eye);
+        if eye_block == self.sorted_from {
+            return;
+        }
+        self.sorted_from = eye_block;
+
+        for chunk in self.chunks.values_mut() {
+            chunk.sort_translucent(&self.world_vertex_buffer, self.eye);
+        }
+    }
+
+    pub fn meshing_mode(&self) -> MeshingMode {
+        self.meshing_mode
+    }
+
+    /// every loaded chunk gets meshed again with the new mode
+    pub fn set_meshing_mode(&mut self, mode: MeshingMode) {
+        self.meshing_mode = mode;
+        self.dirty_chunks.extend(self.chunks.values().map(|chunk| chunk.position()));
+    }
+
+    pub fn ambient_occlusion(&self) -> bool {
+        self.ambient_occlusion
+    }
+
+    /// every loaded chunk gets meshed again with or without ao
+    pub fn set_ambient_occlusion(&mut self, ambient_occlusion: bool) {
+        self.ambient_occlusion = ambient_occlusion;
+        self.dirty_chunks.extend(self.chunks.values().map(|chunk| chunk.position()));
+    }
+
+    /// vertices of all the meshes that are on the gpu right now
+    pub fn vertex_count(&self) -> u64 {
+        self.chunks.values()
+            .flat_map(|chunk| [chunk.get_draw_info(), chunk.get_translucent_draw_info()])
+            .flatten()
+            .map(|draw_info| draw_info.2 / mesher::INDICES_PER_QUAD * mesher::VERTICES_PER_QUAD)
+            .sum()
+    }
+
+    /// writes every chunk that was modified since the last save to disk
+    ///
+    /// they stay marked as edited if it fails so the next save tries again
+    pub fn save(&mut self) {
+        let chunks = self.chunks.values().filter(|chunk| self.modified_chunks.contains(&chunk.position()));
+
+        match self.context.storage().save_chunks(chunks) {
+            Ok(()) => self.modified_chunks.clear(),
+            Err(e) => println!("Failed to save chunks: {e}")
+        }
+    }
+
+    /// saves the ones that were modified all at once
+    fn unload_chunks(&mut self, chunks: &[Chunk]) {
+        let modified = chunks.iter().filter(|chunk| self.modified_chunks.remove(&chunk.position())).collect::<Vec<_>>();
+        let count = modified.len();
+
+        if let Err(e) = self.context.storage().save_chunks(modified) {
+            println!("Failed to save {count} unloaded chunks: {e}");
+        }
+    }
+
+    /// places the features a worker found for a chunk and whatever its neighbours queued up for it
+    ///
+    /// features only get placed once, a chunk that got blocks from features of other chunks or whose
+    /// features grew into other chunks gets saved so it doesnt come back without them (or with them again).
+    /// other loaded chunks that features grew into get marked as dirty, and the blocks that were placed
+    /// in them are given back so their light can be updated
+    fn decorate_chunk(&mut self, key: ChunkPos, blocks: Vec<(GlobalPos, BlockId)>) -> Vec<GlobalPos> {
+        let own_position = key * Chunk::SIZE as i32;
+        let grows_out = blocks.iter().any(|(global_pos, _)| Chunk::locate(*global_pos).0 != own_position);
+
+        let generator = &self.context.generator;
+        let priority = |block: BlockId| generator.decoration_priority(block);
+
+        let placed_elsewhere = decoration::place(&mut self.chunks, &mut self.pending_blocks, key, blocks, priority);
+        let grown_into = self.pending_blocks.apply(self.chunks.get_mut(&key).unwrap(), priority);
+
+        if grows_out || grown_into {
+            self.modified_chunks.insert(own_position);
+        }
+        for global_pos in &placed_elsewhere {
+            let chunk_position = Chunk::locate(*global_pos).0;
+            self.dirty_chunks.insert(chunk_position);
+            self.modified_chunks.insert(chunk_position);
+        }
+
+        placed_elsewhere
+    }
+
+    /// runs `update` on the loaded chunks and marks the chunks whose light changed as dirty
+    fn update_light(&mut self, update: impl FnOnce(&mut LightUpdate)) {
+        let context = &self.context;
+        let surface_height = |x, z| context.generator.surface_height(x, z, context.seed);
+
+        let mut light = LightUpdate::new(&mut self.chunks, &context.registry, &surface_height);
+        update(&mut light);
+        self.dirty_chunks.extend(light.changed_chunks());
+    }
+
+    /// the key of the loaded chunk at `chunk_position`, None if that chunk isnt loaded
+    fn loaded_chunk_key(&self, chunk_position: GlobalPos) -> Option<ChunkPos> {
+        let key = chunk_position / Chunk::SIZE as i32;
+        self.chunks.contains_key(&key).then_some(key)
+    }
+
+    /// positions of the chunks that need new meshes after the block at `global_pos` changed from `old` to `new`,
+    /// the chunk its in and the neighbours it touches
+    ///
+    /// a solid block in place of another one hides the same faces and darkens the same corners,
+    /// anything else could show or hide faces of the neighbours (see `mesher::visible_face`) or change their ao.
+    /// the ao reaches into the chunks diagonal to it too if the block is on an edge or corner
+    fn remeshed_by_edit(global_pos: GlobalPos, old: BlockType, new: BlockType) -> impl Iterator<Item = GlobalPos> {
+        let (chunk_position, local_pos) = Chunk::locate(global_pos);
+        let neighbours_changed = old != BlockType::Solid || new != BlockType::Solid;
+
+        let last = Chunk::SIZE as i8 - 1;
+        let touches = move |direction: &ChunkPos| neighbours_changed && (0..3).all(|axis| match direction[axis] {
+            -1 => local_pos[axis] == 0,
+            1 => local_pos[axis] == last,
+            _ => true
+        });
+
+        std::iter::once(chunk_position).chain(World::neighbour_directions().filter(touches).map(move |direction| chunk_position + direction * Chunk::SIZE as i32))
+    }
+
+    /// the 26 chunks around a chunk, the mesher needs all of them for the ao on the edges and corners
+    fn neighbour_directions() -> impl Iterator<Item = ChunkPos> {
+        (0..27).map(mesher::neighbour_direction).filter(|direction| *direction != glm::vec3(0, 0, 0))
+    }
+
+    /// None if the generator doesnt have biomes
+    pub fn biome_at(&self, position: GlobalPos) -> Option<Biome> {
+        self.context.generator.biome_at(position, self.context.seed)
+    }
+
+    /// None if the generator doesnt know where the surface is
+    pub fn surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        self.context.generator.surface_height(x, z, self.context.seed)
+    }
+
+    pub fn registry(&self) -> &BlockRegistry {
+        &self.context.registry
+    }
+
+    /// the translucent parts of the chunks go last, from the chunk furthest away from the player to the closest
     pub fn draw(&self, camera_buffer_info: vk::DescriptorBufferInfo, atlas_image_info: vk::DescriptorImageInfo) {
         for chunk in self.chunks.values() {
             chunk.write_descriptor(camera_buffer_info, atlas_image_info);
             let chunk_draw_info = chunk.get_draw_info();
             if let Some(chunk_draw_info) = chunk_draw_info {
-                engine::instance::draw(self.world_vertex_buffer.buffer(), chunk_draw_info.0, chunk_draw_info.1, chunk_draw_info.2);
+                engine::instance::draw(self.world_vertex_buffer.buffer(), chunk_draw_info.0, Some(self.quad_index_buffer.buffer()), chunk_draw_info.1, chunk_draw_info.2);
             }
         }
+
+        let mut translucent = self.chunks.values().filter_map(|chunk| {
+            let center = chunk.position().map(|v| v as f32) + glm::vec3(1.0, 1.0, 1.0) * Chunk::SIZE as f32 / 2.0;
+            chunk.get_translucent_draw_info().map(|draw_info| (glm::distance2(&center, &self.eye), draw_info))
+        }).collect::<Vec<_>>();
+        translucent.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (_, chunk_draw_info) in translucent {
+            engine::instance::draw_translucent(self.world_vertex_buffer.buffer(), chunk_draw_info.0, Some(self.quad_index_buffer.buffer()), chunk_draw_info.1, chunk_draw_info.2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::world::{block::BlockType, chunk::GlobalPos};
+    use super::World;
+
+    fn remeshed(edits: &[(GlobalPos, BlockType, BlockType)]) -> HashSet<GlobalPos> {
+        edits.iter().flat_map(|(global_pos, old, new)| World::remeshed_by_edit(*global_pos, *old, *new)).collect()
+    }
+
+    #[test]
+    fn edits_remesh_the_chunks_they_touch() {
+        let (air, solid, cutout) = (BlockType::Air, BlockType::Solid, BlockType::Cutout);
+
+        assert_eq!(remeshed(&[(glm::vec3(5, 5, 5), solid, air)]), HashSet::from([glm::vec3(0, 0, 0)]));
+        // on the border, the neighbour was drawing its faces against this block
+        assert_eq!(remeshed(&[(glm::vec3(0, 5, 5), solid, air)]), HashSet::from([glm::vec3(0, 0, 0), glm::vec3(-20, 0, 0)]));
+        assert_eq!(remeshed(&[(glm::vec3(-1, 5, 5), air, cutout)]), HashSet::from([glm::vec3(-20, 0, 0), glm::vec3(0, 0, 0)]));
+        assert_eq!(remeshed(&[(glm::vec3(19, 19, 19), air, solid)]).len(), 8);
+        assert_eq!(remeshed(&[(glm::vec3(19, 19, 5), air, solid)]).len(), 4);
+
+        // swapping solid blocks only changes the textures of the chunk itself
+        assert_eq!(remeshed(&[(glm::vec3(0, 0, 0), solid, solid)]), HashSet::from([glm::vec3(0, 0, 0)]));
+    }
+
+    #[test]
+    fn edits_in_one_frame_remesh_each_chunk_once() {
+        let edits = (0..20).map(|x| (glm::vec3(x, 3, 7), BlockType::Solid, BlockType::Air)).collect::<Vec<_>>();
+        assert_eq!(remeshed(&edits), HashSet::from([glm::vec3(0, 0, 0), glm::vec3(-20, 0, 0), glm::vec3(20, 0, 0)]));
     }
 }
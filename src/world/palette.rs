@@ -0,0 +1,260 @@
+/// dense, index addressed storage that only stores every unique value once in a palette
+/// and then bit packs the palette indices into `u64`s
+///
+/// if every value is the same (all air, all stone etc.) then no index data is allocated at all
+#[derive(Debug, Clone)]
+pub struct PalettedContainer<T> {
+    len: usize,
+    storage: Storage<T>
+}
+
+#[derive(Debug, Clone)]
+enum Storage<T> {
+    Single(T),
+    Paletted {
+        palette: Vec<T>,
+        bits: u32,
+        data: Vec<u64>
+    }
+}
+
+impl<T: Clone + PartialEq> PalettedContainer<T> {
+    pub fn new(len: usize, value: T) -> PalettedContainer<T> {
+        PalettedContainer {
+            len,
+            storage: Storage::Single(value)
+        }
+    }
+
+    pub fn from_fn(len: usize, mut f: impl FnMut(usize) -> T) -> PalettedContainer<T> {
+        let mut palette: Vec<T> = Vec::new();
+        let mut indices = Vec::with_capacity(len);
+
+        for i in 0..len {
+            let value = f(i);
+
+            let palette_index = match palette.iter().position(|v| *v == value) {
+                Some(palette_index) => palette_index,
+                None => {
+                    palette.push(value);
+                    palette.len() - 1
+                }
+            };
+            indices.push(palette_index as u32);
+        }
+
+        if palette.len() <= 1 {
+            // len of 0 still needs something to hand out, so just let it panic like an empty vec would
+            return PalettedContainer::new(len, palette.pop().expect("PalettedContainer can't be empty"));
+        }
+
+        let bits = bits_needed(palette.len());
+        let mut data = vec![0; words_needed(len, bits)];
+        for (i, palette_index) in indices.into_iter().enumerate() {
+            write_packed(&mut data, bits, i, palette_index);
+        }
+
+        PalettedContainer {
+            len,
+            storage: Storage::Paletted {
+                palette,
+                bits,
+                data
+            }
+        }
+    }
+
+    /// builds a container out of what `palette`, `bits` and `data` gave back, None if the parts dont fit together
+    pub fn from_raw_parts(len: usize, palette: Vec<T>, bits: u32, data: Vec<u64>) -> Option<PalettedContainer<T>> {
+        if bits == 0 {
+            return match <[T; 1]>::try_from(palette) {
+                Ok([value]) => Some(PalettedContainer::new(len, value)),
+                Err(_) => None
+            };
+        }
+
+        if palette.len() < 2 || bits != bits_needed(palette.len()) || data.len() != words_needed(len, bits) {
+            return None;
+        }
+        if (0..len).any(|i| read_packed(&data, bits, i) as usize >= palette.len()) {
+            return None;
+        }
+
+        Some(PalettedContainer {
+            len,
+            storage: Storage::Paletted {
+                palette,
+                bits,
+                data
+            }
+        })
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        assert!(index < self.len, "index {index} out of bounds for PalettedContainer of len {}", self.len);
+
+        match &self.storage {
+            Storage::Single(value) => value,
+            Storage::Paletted { palette, bits, data } => &palette[read_packed(data, *bits, index) as usize]
+        }
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < self.len, "index {index} out of bounds for PalettedContainer of len {}", self.len);
+
+        match &mut self.storage {
+            Storage::Single(current) => {
+                if *current == value {
+                    return;
+                }
+
+                // everything is index 0 (the old value) until told otherwise
+                let mut data = vec![0; words_needed(self.len, 1)];
+                write_packed(&mut data, 1, index, 1);
+
+                self.storage = Storage::Paletted {
+                    palette: vec![current.clone(), value],
+                    bits: 1,
+                    data
+                };
+            }
+            Storage::Paletted { palette, bits, data } => {
+                let palette_index = match palette.iter().position(|v| *v == value) {
+                    Some(palette_index) => palette_index,
+                    None => {
+                        palette.push(value);
+
+                        let needed_bits = bits_needed(palette.len());
+                        if needed_bits > *bits {
+                            *data = repack(data, *bits, needed_bits, self.len);
+                            *bits = needed_bits;
+                        }
+
+                        palette.len() - 1
+                    }
+                };
+
+                write_packed(data, *bits, index, palette_index as u32);
+            }
+        }
+    }
+
+    /// drops palette entries that arent referenced anymore and collapses back into a single value if possible
+    ///
+    /// `set` never shrinks the palette so call this after a bunch of edits
+    pub fn compact(&mut self) {
+        let Storage::Paletted { palette, bits, data } = &self.storage else {
+            return;
+        };
+
+        let mut used = vec![false; palette.len()];
+        for i in 0..self.len {
+            used[read_packed(data, *bits, i) as usize] = true;
+        }
+
+        if used.iter().all(|u| *u) {
+            return;
+        }
+
+        let (palette, bits, data) = (palette.clone(), *bits, data.clone());
+        *self = PalettedContainer::from_fn(self.len, |i| palette[read_packed(&data, bits, i) as usize].clone());
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn palette(&self) -> &[T] {
+        match &self.storage {
+            Storage::Single(value) => std::slice::from_ref(value),
+            Storage::Paletted { palette, .. } => palette
+        }
+    }
+
+    /// bits used per index, 0 when the whole container is a single value
+    pub fn bits(&self) -> u32 {
+        match &self.storage {
+            Storage::Single(_) => 0,
+            Storage::Paletted { bits, .. } => *bits
+        }
+    }
+
+    /// the packed palette indices, empty when the whole container is a single value
+    pub fn data(&self) -> &[u64] {
+        match &self.storage {
+            Storage::Single(_) => &[],
+            Storage::Paletted { data, .. } => data
+        }
+    }
+}
+
+fn bits_needed(palette_len: usize) -> u32 {
+    // at least 1 bit, a palette of 1 is handled by `Storage::Single`
+    (usize::BITS - (palette_len - 1).leading_zeros()).max(1)
+}
+
+/// indices never straddle two words, the leftover bits at the end of each word are just wasted
+fn words_needed(len: usize, bits: u32) -> usize {
+    let per_word = (u64::BITS / bits) as usize;
+    len.div_ceil(per_word)
+}
+
+fn read_packed(data: &[u64], bits: u32, index: usize) -> u32 {
+    let per_word = (u64::BITS / bits) as usize;
+    let shift = (index % per_word) as u32 * bits;
+    let mask = (1u64 << bits) - 1;
+
+    ((data[index / per_word] >> shift) & mask) as u32
+}
+
+fn write_packed(data: &mut [u64], bits: u32, index: usize, value: u32) {
+    let per_word = (u64::BITS / bits) as usize;
+    let shift = (index % per_word) as u32 * bits;
+    let mask = (1u64 << bits) - 1;
+
+    let word = &mut data[index / per_word];
+    *word = (*word & !(mask << shift)) | ((value as u64 & mask) << shift);
+}
+
+fn repack(data: &[u64], old_bits: u32, new_bits: u32, len: usize) -> Vec<u64> {
+    let mut new_data = vec![0; words_needed(len, new_bits)];
+    for i in 0..len {
+        write_packed(&mut new_data, new_bits, i, read_packed(data, old_bits, i));
+    }
+
+    new_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PalettedContainer;
+
+    #[test]
+    fn compact_drops_unused_values() {
+        let mut container = PalettedContainer::new(100, 0u16);
+        for i in 0..10 {
+            container.set(i, i as u16);
+        }
+        assert_eq!(container.palette().len(), 10);
+
+        for i in 5..10 {
+            container.set(i, 0);
+        }
+        container.compact();
+        assert_eq!(container.palette().len(), 5);
+        assert!((0..100).all(|i| *container.get(i) == if i < 5 { i as u16 } else { 0 }));
+
+        for i in 0..5 {
+            container.set(i, 0);
+        }
+        container.compact();
+        assert_eq!(container.palette(), &[0]);
+        assert_eq!(container.bits(), 0);
+        assert_eq!((container.len(), container.is_empty()), (100, false));
+        assert!(PalettedContainer::new(0, 0u16).is_empty());
+    }
+}
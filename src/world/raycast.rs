@@ -0,0 +1,183 @@
+use super::chunk::GlobalPos;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RaycastHit {
+    /// the block that got hit
+    pub position: GlobalPos,
+    /// points out of the face that got hit, 0, 0, 0 if the ray started inside of the block
+    pub normal: glm::IVec3,
+    /// how far along the ray the face is
+    pub distance: f32,
+    /// the block in front of the face, where a new block would get placed
+    pub adjacent: GlobalPos
+}
+
+/// turns a position in camera space (like `Camera::position`) into block space
+///
+/// camera space y is flipped and the meshes are shifted back by 1 along z (see `mesher::build_mesh`)
+pub fn to_block_space(position: glm::Vec3) -> glm::Vec3 {
+    glm::vec3(position.x, -position.y, position.z + 1.0)
+}
+
+/// the other way around from `to_block_space`
+pub fn to_camera_space(position: glm::Vec3) -> glm::Vec3 {
+    glm::vec3(position.x, -position.y, position.z - 1.0)
+}
+
+/// walks through every block the ray touches in order (amanatides & woo) until `is_solid` says yes
+///
+/// everything is in block space, block x, y, z goes from x, y, z to x + 1, y + 1, z + 1
+pub fn raycast(origin: glm::Vec3, direction: glm::Vec3, max_distance: f32, is_solid: impl Fn(GlobalPos) -> bool) -> Option<RaycastHit> {
+    let length = glm::length(&direction);
+    if length == 0.0 || !length.is_finite() {
+        return None;
+    }
+    let direction = direction / length;
+
+    let mut position = glm::vec3(origin.x.floor() as i32, origin.y.floor() as i32, origin.z.floor() as i32);
+    let step = glm::vec3(direction.x.signum() as i32, direction.y.signum() as i32, direction.z.signum() as i32);
+
+    // how far along the ray it takes to go through a whole block on each axis
+    let mut delta = glm::vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+    // how far along the ray the next block border on each axis is
+    let mut next = glm::vec3(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+
+    for axis in 0..3 {
+        if direction[axis] != 0.0 {
+            delta[axis] = 1.0 / direction[axis].abs();
+
+            let border = if direction[axis] > 0.0 { position[axis] as f32 + 1.0 } else { position[axis] as f32 };
+            next[axis] = (border - origin[axis]).abs() * delta[axis];
+        }
+    }
+
+    let mut normal = glm::vec3(0, 0, 0);
+    let mut distance = 0.0;
+
+    loop {
+        if is_solid(position) {
+            return Some(RaycastHit {
+                position,
+                normal,
+                distance,
+                adjacent: position + normal
+            });
+        }
+
+        let axis = if next.x < next.y && next.x < next.z {
+            0
+        } else if next.y < next.z {
+            1
+        } else {
+            2
+        };
+
+        distance = next[axis];
+        if distance > max_distance {
+            return None;
+        }
+
+        position[axis] += step[axis];
+        next[axis] += delta[axis];
+
+        normal = glm::vec3(0, 0, 0);
+        normal[axis] = -step[axis];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::HashSet};
+    use crate::world::chunk::{Chunk, GlobalPos};
+    use super::{raycast, RaycastHit};
+
+    fn cast(solid: &[GlobalPos], origin: glm::Vec3, direction: glm::Vec3, max_distance: f32) -> Option<RaycastHit> {
+        let solid = solid.iter().copied().collect::<HashSet<_>>();
+        raycast(origin, direction, max_distance, |position| solid.contains(&position))
+    }
+
+    fn assert_hit(hit: Option<RaycastHit>, position: GlobalPos, normal: glm::IVec3, distance: f32) {
+        let hit = hit.expect("ray should hit");
+        assert_eq!((hit.position, hit.normal, hit.adjacent), (position, normal, position + normal));
+        assert!((hit.distance - distance).abs() < 1e-5, "distance {} should be {distance}", hit.distance);
+    }
+
+    #[test]
+    fn axis_aligned() {
+        let solid = [glm::vec3(5, 0, 0), glm::vec3(-5, 0, 0), glm::vec3(0, 3, 0), glm::vec3(0, -3, 0), glm::vec3(0, 0, 7), glm::vec3(0, 0, -7)];
+        let origin = glm::vec3(0.5, 0.5, 0.5);
+
+        assert_hit(cast(&solid, origin, glm::vec3(1.0, 0.0, 0.0), 10.0), glm::vec3(5, 0, 0), glm::vec3(-1, 0, 0), 4.5);
+        assert_hit(cast(&solid, origin, glm::vec3(-1.0, 0.0, 0.0), 10.0), glm::vec3(-5, 0, 0), glm::vec3(1, 0, 0), 4.5);
+        assert_hit(cast(&solid, origin, glm::vec3(0.0, 2.0, 0.0), 10.0), glm::vec3(0, 3, 0), glm::vec3(0, -1, 0), 2.5);
+        assert_hit(cast(&solid, origin, glm::vec3(0.0, -1.0, 0.0), 10.0), glm::vec3(0, -3, 0), glm::vec3(0, 1, 0), 2.5);
+        assert_hit(cast(&solid, origin, glm::vec3(0.0, 0.0, 1.0), 10.0), glm::vec3(0, 0, 7), glm::vec3(0, 0, -1), 6.5);
+        assert_hit(cast(&solid, origin, glm::vec3(0.0, 0.0, -1.0), 10.0), glm::vec3(0, 0, -7), glm::vec3(0, 0, 1), 6.5);
+    }
+
+    #[test]
+    fn diagonal() {
+        let hit = cast(&[glm::vec3(3, 3, 0)], glm::vec3(0.25, 0.5, 0.5), glm::vec3(1.0, 1.0, 0.0), 10.0);
+        assert_hit(hit, glm::vec3(3, 3, 0), glm::vec3(-1, 0, 0), 2.75 * 2f32.sqrt());
+
+        // every block on the way shares a face with the one before it, corners dont get skipped
+        let visited = RefCell::new(Vec::new());
+        raycast(glm::vec3(0.3, 0.6, 0.1), glm::vec3(0.4, -0.7, 1.3), 20.0, |position| {
+            visited.borrow_mut().push(position);
+            false
+        });
+        let visited = visited.into_inner();
+        assert!(visited.len() > 20);
+        for pair in visited.windows(2) {
+            assert_eq!((pair[1] - pair[0]).abs().sum(), 1, "{:?} to {:?}", pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let solid = [glm::vec3(-3, -2, -1), glm::vec3(-1, -5, -1)];
+        let origin = glm::vec3(-0.5, -1.5, -0.5);
+
+        assert_hit(cast(&solid, origin, glm::vec3(-1.0, 0.0, 0.0), 10.0), glm::vec3(-3, -2, -1), glm::vec3(1, 0, 0), 1.5);
+        assert_hit(cast(&solid, origin, glm::vec3(0.0, -1.0, 0.0), 10.0), glm::vec3(-1, -5, -1), glm::vec3(0, 1, 0), 2.5);
+    }
+
+    #[test]
+    fn crosses_chunk_borders() {
+        // only the first block of each chunk is solid
+        let is_solid = |position: GlobalPos| Chunk::locate(position).1 == glm::vec3(0, 0, 0);
+        let size = Chunk::SIZE as i32;
+
+        let hit = raycast(glm::vec3(2.5, 0.5, 0.5), glm::vec3(1.0, 0.0, 0.0), 100.0, is_solid);
+        assert_hit(hit, glm::vec3(size, 0, 0), glm::vec3(-1, 0, 0), size as f32 - 2.5);
+        assert_eq!(Chunk::locate(hit.unwrap().adjacent).0, glm::vec3(0, 0, 0));
+
+        let hit = raycast(glm::vec3(0.5, 0.5, -0.5), glm::vec3(0.0, 0.0, -1.0), 100.0, is_solid);
+        assert_hit(hit, glm::vec3(0, 0, -size), glm::vec3(0, 0, 1), size as f32 - 1.5);
+    }
+
+    #[test]
+    fn starts_inside_a_block() {
+        let hit = cast(&[glm::vec3(2, -1, 4)], glm::vec3(2.9, -0.1, 4.5), glm::vec3(1.0, 0.0, 0.0), 10.0);
+        assert_hit(hit, glm::vec3(2, -1, 4), glm::vec3(0, 0, 0), 0.0);
+    }
+
+    #[test]
+    fn max_distance() {
+        let solid = [glm::vec3(5, 0, 0)];
+        let origin = glm::vec3(0.5, 0.5, 0.5);
+
+        assert!(cast(&solid, origin, glm::vec3(1.0, 0.0, 0.0), 4.4).is_none());
+        assert_hit(cast(&solid, origin, glm::vec3(1.0, 0.0, 0.0), 4.5), glm::vec3(5, 0, 0), glm::vec3(-1, 0, 0), 4.5);
+        // the length of the direction doesnt matter
+        assert_hit(cast(&solid, origin, glm::vec3(0.1, 0.0, 0.0), 4.5), glm::vec3(5, 0, 0), glm::vec3(-1, 0, 0), 4.5);
+        assert!(cast(&[], origin, glm::vec3(1.0, 1.0, 1.0), 50.0).is_none());
+    }
+
+    #[test]
+    fn zero_direction() {
+        let solid = [glm::vec3(0, 0, 0)];
+        assert!(cast(&solid, glm::vec3(0.5, 0.5, 0.5), glm::vec3(0.0, 0.0, 0.0), 10.0).is_none());
+        assert!(cast(&solid, glm::vec3(0.5, 0.5, 0.5), glm::vec3(f32::NAN, 0.0, 0.0), 10.0).is_none());
+    }
+}
@@ -0,0 +1,626 @@
+use std::{collections::{HashMap, HashSet, hash_map::Entry}, fmt::{self, Display}, fs::{self, File, OpenOptions}, io::{self, Read, Seek, SeekFrom, Write}, path::{Path, PathBuf}, sync::Arc};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use super::{chunk::{Chunk, GlobalPos}, palette::PalettedContainer, id_map::BlockIdMap, migration::{self, RawChunk}, save::{self, Journal}};
+
+/// chunks per axis in one region file
+pub const REGION_SIZE: i32 = 16;
+const CHUNKS_PER_REGION: usize = (REGION_SIZE * REGION_SIZE * REGION_SIZE) as usize;
+
+/// chunks always start at the beginning of a sector so they can be rewritten in place
+const SECTOR_SIZE: u64 = 4096;
+const MAGIC: [u8; 4] = *b"RCRG";
+/// version 1 didnt have a version in front of every chunk
+const VERSION: u32 = 2;
+/// magic, version and then (first sector, length in bytes) for every chunk in the region
+const HEADER_SIZE: u64 = 8 + CHUNKS_PER_REGION as u64 * 8;
+const HEADER_SECTORS: u32 = HEADER_SIZE.div_ceil(SECTOR_SIZE) as u32;
+
+const COMPRESSION_ZLIB: u8 = 1;
+
+const JOURNAL_NAME: &str = "save.journal";
+
+#[derive(Debug)]
+pub enum RegionError {
+    Io(io::Error),
+    Corrupt {
+        path: PathBuf,
+        message: String
+    }
+}
+
+impl Display for RegionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegionError::Io(e) => write!(f, "couldn't access region file: {e}"),
+            RegionError::Corrupt { path, message } => write!(f, "region file {} is corrupt: {message}", path.display())
+        }
+    }
+}
+
+impl std::error::Error for RegionError {}
+
+impl From<io::Error> for RegionError {
+    fn from(e: io::Error) -> RegionError {
+        RegionError::Io(e)
+    }
+}
+
+/// all the region files of one world, every chunk is compressed on its own
+///
+/// region files stay open once theyve been used
+pub struct RegionStorage {
+    directory: PathBuf,
+    regions: HashMap<GlobalPos, RegionFile>,
+    /// shared with every `StoredChunk` so they can be decoded without the storage
+    block_ids: Arc<BlockIdMap>
+}
+
+/// a chunk straight out of its region file, decoding it is the slow part
+/// so it doesnt need the storage (and whatever lock its behind) anymore for that
+pub struct StoredChunk {
+    position: GlobalPos,
+    path: PathBuf,
+    bytes: Vec<u8>,
+    block_ids: Arc<BlockIdMap>
+}
+
+impl StoredChunk {
+    pub fn decode(&self) -> Result<Chunk, RegionError> {
+        decode_chunk(self.position, &self.bytes, &self.block_ids).map_err(|message| RegionError::Corrupt {
+            path: self.path.clone(),
+            message: format!("chunk at {:?}: {message}", self.position)
+        })
+    }
+}
+
+impl RegionStorage {
+    /// creates the directory if it doesnt exist yet and finishes the last save if it got interrupted
+    pub fn open(directory: impl AsRef<Path>, block_ids: BlockIdMap) -> Result<RegionStorage, RegionError> {
+        fs::create_dir_all(directory.as_ref())?;
+
+        let mut storage = RegionStorage {
+            directory: directory.as_ref().to_path_buf(),
+            regions: HashMap::new(),
+            block_ids: Arc::new(block_ids)
+        };
+        storage.recover()?;
+
+        Ok(storage)
+    }
+
+    /// None if the chunk was never saved
+    pub fn load_chunk(&mut self, chunk_position: GlobalPos) -> Result<Option<Chunk>, RegionError> {
+        self.read_chunk(chunk_position)?.map(|stored| stored.decode()).transpose()
+    }
+
+    /// like `load_chunk` but leaves the decoding to the caller
+    pub fn read_chunk(&mut self, chunk_position: GlobalPos) -> Result<Option<StoredChunk>, RegionError> {
+        let (region_position, index) = RegionStorage::locate(chunk_position);
+        // nothing in this region was ever saved
+        let Some(region) = RegionStorage::region(&mut self.regions, &self.directory, region_position, false)? else {
+            return Ok(None);
+        };
+
+        Ok(region.read(index)?.map(|bytes| StoredChunk {
+            position: chunk_position,
+            path: region.path.clone(),
+            bytes,
+            block_ids: self.block_ids.clone()
+        }))
+    }
+
+    /// only looks at the table of the region, not the chunk itself
+    pub fn contains(&mut self, chunk_position: GlobalPos) -> Result<bool, RegionError> {
+        let (region_position, index) = RegionStorage::locate(chunk_position);
+        let Some(region) = RegionStorage::region(&mut self.regions, &self.directory, region_position, false)? else {
+            return Ok(false);
+        };
+
+        Ok(region.table[index].0 != 0)
+    }
+
+    /// writes all of the chunks or (after `recover`) none of them, even if the game crashes halfway through
+    pub fn save_chunks<'a>(&mut self, chunks: impl IntoIterator<Item = &'a Chunk>) -> Result<(), RegionError> {
+        let journal = Journal {
+            entries: chunks.into_iter().map(|chunk| (chunk.position(), encode_chunk(chunk, &self.block_ids))).collect()
+        };
+        if journal.entries.is_empty() {
+            return Ok(());
+        }
+
+        let journal_path = self.directory.join(JOURNAL_NAME);
+        save::write_atomic(&journal_path, &journal.encode())?;
+        self.apply(&journal)?;
+        fs::remove_file(journal_path)?;
+
+        Ok(())
+    }
+
+    /// redoes the last save if its journal is still there
+    fn recover(&mut self) -> Result<(), RegionError> {
+        let journal_path = self.directory.join(JOURNAL_NAME);
+
+        // the journal itself never got finished so the region files werent touched yet
+        let temp_path = save::temp_path(&journal_path);
+        if temp_path.exists() {
+            fs::remove_file(temp_path)?;
+        }
+
+        if !journal_path.exists() {
+            return Ok(());
+        }
+
+        match Journal::decode(&fs::read(&journal_path)?) {
+            Ok(journal) => {
+                println!("Last save got interrupted, writing {} chunks again", journal.entries.len());
+                self.apply(&journal)?;
+            }
+            // its only ever written atomically so this means the disk is broken, nothing left to recover
+            Err(message) => println!("Save journal is corrupt, ignoring it: {message}")
+        }
+
+        fs::remove_file(journal_path)?;
+
+        Ok(())
+    }
+
+    fn apply(&mut self, journal: &Journal) -> Result<(), RegionError> {
+        let mut touched = HashSet::new();
+
+        for (chunk_position, bytes) in &journal.entries {
+            let (region_position, index) = RegionStorage::locate(*chunk_position);
+            RegionStorage::region(&mut self.regions, &self.directory, region_position, true)?.unwrap().write(index, bytes)?;
+            touched.insert(region_position);
+        }
+
+        // has to be on disk before the journal gets deleted
+        for region_position in touched {
+            self.regions.get(&region_position).unwrap().file.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// position of the region (in regions) and the index of the chunk inside of it
+    fn locate(chunk_position: GlobalPos) -> (GlobalPos, usize) {
+        let chunk = chunk_position / Chunk::SIZE as i32;
+        let region = glm::vec3(chunk.x.div_euclid(REGION_SIZE), chunk.y.div_euclid(REGION_SIZE), chunk.z.div_euclid(REGION_SIZE));
+        let local = chunk - region * REGION_SIZE;
+
+        (region, (local.x + local.z * REGION_SIZE + local.y * REGION_SIZE * REGION_SIZE) as usize)
+    }
+
+    /// None if the region file doesnt exist and `create` is false, reading shouldnt leave empty files everywhere
+    ///
+    /// takes the fields instead of self so the block ids can still be used while a region is borrowed
+    fn region<'a>(regions: &'a mut HashMap<GlobalPos, RegionFile>, directory: &Path, region_position: GlobalPos, create: bool) -> Result<Option<&'a mut RegionFile>, RegionError> {
+        match regions.entry(region_position) {
+            Entry::Occupied(entry) => Ok(Some(entry.into_mut())),
+            Entry::Vacant(entry) => {
+                let path = directory.join(format!("r.{}.{}.{}.region", region_position.x, region_position.y, region_position.z));
+                if !create && !path.exists() {
+                    return Ok(None);
+                }
+
+                Ok(Some(entry.insert(RegionFile::open(path)?)))
+            }
+        }
+    }
+}
+
+struct RegionFile {
+    path: PathBuf,
+    file: File,
+    /// (first sector, length in bytes) of every chunk, sector 0 means the chunk isnt in the file
+    table: Vec<(u32, u32)>,
+    /// length of the file in sectors, new chunks get added at the end
+    sectors: u32
+}
+
+impl RegionFile {
+    fn open(path: PathBuf) -> Result<RegionFile, RegionError> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&path)?;
+        let length = file.metadata()?.len();
+
+        let corrupt = |message: &str| RegionError::Corrupt {
+            path: path.clone(),
+            message: message.to_string()
+        };
+
+        if length == 0 {
+            let mut header = Vec::with_capacity((HEADER_SECTORS as u64 * SECTOR_SIZE) as usize);
+            header.extend_from_slice(&MAGIC);
+            header.extend_from_slice(&VERSION.to_le_bytes());
+            header.resize((HEADER_SECTORS as u64 * SECTOR_SIZE) as usize, 0);
+            file.write_all(&header)?;
+
+            return Ok(RegionFile {
+                path,
+                file,
+                table: vec![(0, 0); CHUNKS_PER_REGION],
+                sectors: HEADER_SECTORS
+            });
+        }
+
+        if length < HEADER_SIZE {
+            return Err(corrupt("file is shorter than the header"));
+        }
+
+        let mut header = vec![0; HEADER_SIZE as usize];
+        file.read_exact(&mut header)?;
+
+        if header[0..4] != MAGIC {
+            return Err(corrupt("not a region file"));
+        }
+        let version = u32::from_le_bytes(header[4..8].try_into().unwrap());
+        if version == 0 || version > VERSION {
+            return Err(corrupt(&format!("unsupported region version {version}")));
+        }
+
+        let sectors = length.div_ceil(SECTOR_SIZE) as u32;
+        let table = header[8..].chunks_exact(8).map(|entry| {
+            (u32::from_le_bytes(entry[0..4].try_into().unwrap()), u32::from_le_bytes(entry[4..8].try_into().unwrap()))
+        }).collect::<Vec<_>>();
+
+        for (sector, len) in &table {
+            if *sector != 0 && (*sector < HEADER_SECTORS || *sector as u64 * SECTOR_SIZE + *len as u64 > length) {
+                return Err(corrupt("chunk table points outside of the file"));
+            }
+        }
+
+        if version == 1 {
+            drop(file);
+            RegionFile::upgrade_v1(&path, &table)?;
+
+            return RegionFile::open(path);
+        }
+
+        Ok(RegionFile {
+            path,
+            file,
+            table,
+            sectors
+        })
+    }
+
+    /// rewrites the whole region into a new file with the chunk version in front of every chunk
+    ///
+    /// the old file only gets replaced once the new one is complete
+    fn upgrade_v1(path: &Path, table: &[(u32, u32)]) -> Result<(), RegionError> {
+        println!("Upgrading {} from region version 1", path.display());
+
+        let mut old_file = File::open(path)?;
+        let temp_path = path.with_extension("region.upgrade");
+        // left over from an upgrade that got interrupted
+        if temp_path.exists() {
+            fs::remove_file(&temp_path)?;
+        }
+
+        let mut new_region = RegionFile::open(temp_path.clone())?;
+        for (index, (sector, len)) in table.iter().enumerate() {
+            if *sector == 0 {
+                continue;
+            }
+
+            let mut bytes = 1u16.to_le_bytes().to_vec();
+            bytes.resize(2 + *len as usize, 0);
+            old_file.seek(SeekFrom::Start(*sector as u64 * SECTOR_SIZE))?;
+            old_file.read_exact(&mut bytes[2..])?;
+
+            new_region.write(index, &bytes)?;
+        }
+        new_region.file.sync_all()?;
+
+        drop(old_file);
+        drop(new_region);
+        fs::rename(temp_path, path)?;
+
+        Ok(())
+    }
+
+    fn read(&mut self, index: usize) -> Result<Option<Vec<u8>>, RegionError> {
+        let (sector, len) = self.table[index];
+        if sector == 0 {
+            return Ok(None);
+        }
+
+        let mut bytes = vec![0; len as usize];
+        self.file.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))?;
+        self.file.read_exact(&mut bytes)?;
+
+        Ok(Some(bytes))
+    }
+
+    fn write(&mut self, index: usize, bytes: &[u8]) -> Result<(), RegionError> {
+        let (old_sector, old_len) = self.table[index];
+        let needed = (bytes.len() as u64).div_ceil(SECTOR_SIZE) as u32;
+
+        // the old sectors get reused if it still fits, otherwise theyre wasted until the region gets rewritten
+        let sector = if old_sector != 0 && (old_len as u64).div_ceil(SECTOR_SIZE) as u32 >= needed {
+            old_sector
+        } else {
+            self.sectors += needed;
+            self.sectors - needed
+        };
+
+        let mut padded = bytes.to_vec();
+        padded.resize((needed as u64 * SECTOR_SIZE) as usize, 0);
+        self.file.seek(SeekFrom::Start(sector as u64 * SECTOR_SIZE))?;
+        self.file.write_all(&padded)?;
+
+        let mut entry = [0; 8];
+        entry[0..4].copy_from_slice(&sector.to_le_bytes());
+        entry[4..8].copy_from_slice(&(bytes.len() as u32).to_le_bytes());
+        self.file.seek(SeekFrom::Start(8 + index as u64 * 8))?;
+        self.file.write_all(&entry)?;
+
+        self.table[index] = (sector, bytes.len() as u32);
+
+        Ok(())
+    }
+}
+
+/// chunk version, compression type and then the compressed palette (in saved ids), bits per index and packed indices
+pub fn encode_chunk(chunk: &Chunk, block_ids: &BlockIdMap) -> Vec<u8> {
+    // edits only ever grow the palette, blocks that were all removed again shouldnt end up on disk
+    let mut blocks = chunk.blocks().clone();
+    blocks.compact();
+
+    let mut raw = Vec::new();
+    raw.extend_from_slice(&(blocks.palette().len() as u16).to_le_bytes());
+    for block in blocks.palette() {
+        raw.extend_from_slice(&block_ids.to_saved(*block).to_le_bytes());
+    }
+    raw.push(blocks.bits() as u8);
+    raw.extend_from_slice(&(blocks.data().len() as u32).to_le_bytes());
+    for word in blocks.data() {
+        raw.extend_from_slice(&word.to_le_bytes());
+    }
+
+    let mut header = migration::CHUNK_VERSION.to_le_bytes().to_vec();
+    header.push(COMPRESSION_ZLIB);
+
+    let mut encoder = ZlibEncoder::new(header, Compression::default());
+    // writing into a vec cant fail
+    encoder.write_all(&raw).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// upgrades old chunks and turns the saved ids back into registry ids
+pub fn decode_chunk(position: GlobalPos, bytes: &[u8], block_ids: &BlockIdMap) -> Result<Chunk, String> {
+    let mut reader = ByteReader { bytes };
+    let version = u16::from_le_bytes(reader.take()?);
+    let [compression] = reader.take()?;
+    if compression != COMPRESSION_ZLIB {
+        return Err(format!("unknown compression type {compression}"));
+    }
+
+    let mut raw = Vec::new();
+    ZlibDecoder::new(reader.bytes).read_to_end(&mut raw).map_err(|e| format!("couldn't decompress chunk: {e}"))?;
+
+    let mut reader = ByteReader { bytes: &raw };
+    let palette_len = u16::from_le_bytes(reader.take()?) as usize;
+    let palette = (0..palette_len).map(|_| reader.take().map(u16::from_le_bytes)).collect::<Result<Vec<_>, _>>()?;
+    let [bits] = reader.take()?;
+    let word_count = u32::from_le_bytes(reader.take()?) as usize;
+    let data = (0..word_count).map(|_| reader.take().map(u64::from_le_bytes)).collect::<Result<Vec<_>, _>>()?;
+
+    if !reader.bytes.is_empty() {
+        return Err(format!("{} bytes left over after the chunk", reader.bytes.len()));
+    }
+
+    let chunk = migration::migrate_chunk(RawChunk { palette, bits: bits as u32, data }, version)?;
+    let palette = chunk.palette.iter().map(|id| block_ids.to_registry(*id)).collect();
+
+    let blocks = PalettedContainer::from_raw_parts(Chunk::VOLUME, palette, chunk.bits, chunk.data).ok_or_else(|| "block data doesnt match the palette".to_string())?;
+
+    Ok(Chunk::from_blocks(position, blocks))
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8]
+}
+
+impl ByteReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        if self.bytes.len() < N {
+            return Err("chunk ends too early".to_string());
+        }
+
+        let (taken, rest) = self.bytes.split_at(N);
+        self.bytes = rest;
+
+        Ok(taken.try_into().unwrap())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{fs, path::PathBuf};
+    use crate::{json::JsonValue, world::{block::{Block, BlockId, BlockType}, chunk::Chunk, id_map::BlockIdMap, level::LevelData, registry::BlockRegistry}};
+    use super::{decode_chunk, encode_chunk, RegionFile, RegionStorage, HEADER_SECTORS, SECTOR_SIZE, VERSION};
+
+    /// an empty directory only this test uses
+    pub(crate) fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("rustcraft-test-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+
+        directory
+    }
+
+    fn block_ids() -> BlockIdMap {
+        let mut registry = BlockRegistry::new();
+        registry.register(Block::new("Stone", "stone", BlockType::Solid, glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0), glm::vec2(0.3, 0.0)));
+        registry.register(Block::new("Dirt", "dirt", BlockType::Solid, glm::vec2(0.2, 0.0), glm::vec2(0.2, 0.0), glm::vec2(0.2, 0.0)));
+
+        BlockIdMap::new(Vec::new(), &registry)
+    }
+
+    #[test]
+    fn loading_doesnt_create_files() {
+        let directory = test_directory("loading-doesnt-create-files");
+        let mut storage = RegionStorage::open(&directory, block_ids()).unwrap();
+
+        assert!(storage.load_chunk(glm::vec3(0, 0, 0)).unwrap().is_none());
+        assert!(storage.load_chunk(glm::vec3(-400, 20, 1000)).unwrap().is_none());
+        assert!(!storage.contains(glm::vec3(20, 0, 0)).unwrap());
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 0);
+
+        let chunk = Chunk::new(glm::vec3(20, 0, 0), |_| BlockId(1));
+        storage.save_chunks([&chunk]).unwrap();
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+        assert!(storage.load_chunk(glm::vec3(0, 0, 0)).unwrap().is_none());
+        assert_eq!(storage.load_chunk(glm::vec3(20, 0, 0)).unwrap().unwrap().get_block(glm::vec3(3, 4, 5)), Some(BlockId(1)));
+        assert_eq!([glm::vec3(0, 0, 0), glm::vec3(20, 0, 0)].map(|position| storage.contains(position).unwrap()), [false, true]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    fn assert_same_blocks(a: &Chunk, b: &Chunk) {
+        assert_eq!(a.position(), b.position());
+        assert_eq!(a.blocks().palette(), b.blocks().palette());
+        assert_eq!(a.blocks().bits(), b.blocks().bits());
+        assert!((0..Chunk::VOLUME).all(|i| a.blocks().get(i) == b.blocks().get(i)));
+    }
+
+    #[test]
+    fn chunks_round_trip() {
+        let block_ids = block_ids();
+
+        let single = Chunk::new(glm::vec3(-20, 40, 0), |_| BlockId(2));
+        let decoded = decode_chunk(single.position(), &encode_chunk(&single, &block_ids), &block_ids).unwrap();
+        assert_same_blocks(&single, &decoded);
+        assert_eq!(decoded.blocks().bits(), 0);
+
+        let multi = Chunk::new(glm::vec3(0, -20, 60), |pos| BlockId(((pos.x * 7 + pos.y * 3 + pos.z).rem_euclid(3)) as u16));
+        let decoded = decode_chunk(multi.position(), &encode_chunk(&multi, &block_ids), &block_ids).unwrap();
+        assert_same_blocks(&multi, &decoded);
+
+        let bytes = encode_chunk(&multi, &block_ids);
+        assert!(decode_chunk(multi.position(), &bytes[..bytes.len() - 1], &block_ids).is_err());
+        assert!(decode_chunk(multi.position(), &bytes[..2], &block_ids).is_err());
+    }
+
+    #[test]
+    fn saved_palettes_are_compact() {
+        let block_ids = block_ids();
+
+        let mut chunk = Chunk::new(glm::vec3(0, 0, 0), |_| BlockId::AIR);
+        chunk.set_block(glm::vec3(1, 1, 1), BlockId(1));
+        chunk.set_block(glm::vec3(1, 1, 1), BlockId::AIR);
+        assert_eq!(chunk.blocks().palette().len(), 2);
+
+        let decoded = decode_chunk(chunk.position(), &encode_chunk(&chunk, &block_ids), &block_ids).unwrap();
+        assert_eq!(decoded.blocks().palette(), &[BlockId::AIR]);
+    }
+
+    #[test]
+    fn region_file_write_rewrite_grow_reopen() {
+        let directory = test_directory("region-file");
+        let path = directory.join("r.0.0.0.region");
+
+        let mut region = RegionFile::open(path.clone()).unwrap();
+        assert_eq!(region.sectors, HEADER_SECTORS);
+        assert_eq!(region.read(5).unwrap(), None);
+
+        let small = vec![1; 100];
+        region.write(5, &small).unwrap();
+        region.write(6, &[2; 10]).unwrap();
+        assert_eq!(region.read(5).unwrap(), Some(small));
+        let first_sector = region.table[5].0;
+        assert_eq!(first_sector, HEADER_SECTORS);
+
+        // still fits into the same sector
+        let rewritten = vec![3; SECTOR_SIZE as usize];
+        region.write(5, &rewritten).unwrap();
+        assert_eq!(region.table[5].0, first_sector);
+        assert_eq!(region.read(5).unwrap(), Some(rewritten));
+
+        // needs more sectors so it moves to the end
+        let grown = (0..SECTOR_SIZE as usize * 2 + 1).map(|i| i as u8).collect::<Vec<_>>();
+        region.write(5, &grown).unwrap();
+        assert_eq!(region.table[5].0, HEADER_SECTORS + 2);
+        assert_eq!(region.sectors, HEADER_SECTORS + 5);
+        assert_eq!(region.read(5).unwrap(), Some(grown.clone()));
+        assert_eq!(region.read(6).unwrap(), Some(vec![2; 10]));
+        drop(region);
+
+        let mut region = RegionFile::open(path).unwrap();
+        assert_eq!(region.sectors, HEADER_SECTORS + 5);
+        assert_eq!(region.read(5).unwrap(), Some(grown));
+        assert_eq!(region.read(6).unwrap(), Some(vec![2; 10]));
+        assert_eq!(region.read(7).unwrap(), None);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn storage_reopens() {
+        let directory = test_directory("storage-reopens");
+        let chunks = [
+            Chunk::new(glm::vec3(0, 0, 0), |pos| BlockId((pos.y < 5) as u16)),
+            // different region
+            Chunk::new(glm::vec3(-20, -20, -20), |pos| BlockId((pos.x.rem_euclid(2) + 1) as u16))
+        ];
+
+        let mut storage = RegionStorage::open(&directory, block_ids()).unwrap();
+        storage.save_chunks(&chunks).unwrap();
+        drop(storage);
+
+        let mut storage = RegionStorage::open(&directory, block_ids()).unwrap();
+        for chunk in &chunks {
+            assert_same_blocks(chunk, &storage.load_chunk(chunk.position()).unwrap().unwrap());
+        }
+
+        // read chunks can still be decoded once the storage is gone
+        let stored = chunks.iter().map(|chunk| storage.read_chunk(chunk.position()).unwrap().unwrap()).collect::<Vec<_>>();
+        drop(storage);
+        for (chunk, stored) in chunks.iter().zip(stored) {
+            assert_same_blocks(chunk, &stored.decode().unwrap());
+        }
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    /// has the legacy ids 3 (stone) for y < 5, 1 (grass_block) at y = 5 and 10 (oak_log) at 1, 6, 1 in chunk 20, 0, 0
+    /// and 5 (snow) everywhere in chunk 0, 40, 60
+    const REGION_V1: &[u8] = include_bytes!("../../tests/fixtures/r.0.0.0.region");
+
+    #[test]
+    fn upgrades_v1_regions() {
+        let directory = test_directory("upgrades-v1-regions");
+        let path = directory.join("r.0.0.0.region");
+        fs::write(&path, REGION_V1).unwrap();
+
+        // worlds from back then have the legacy ids, the registry is in a different order by now
+        let level = LevelData::from_json(&JsonValue::parse(include_str!("../../tests/fixtures/level_v1.json")).unwrap()).unwrap();
+        let mut registry = BlockRegistry::new();
+        for dev_name in ["oak_log", "snow", "grass_block", "stone"] {
+            registry.register(Block::new(dev_name, dev_name, BlockType::Solid, glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0), glm::vec2(0.0, 0.0)));
+        }
+        let block_ids = BlockIdMap::new(level.block_ids, &registry);
+        let id = |dev_name| registry.id(dev_name).unwrap();
+
+        for _ in 0..2 {
+            let mut storage = RegionStorage::open(&directory, block_ids.clone()).unwrap();
+
+            let chunk = storage.load_chunk(glm::vec3(20, 0, 0)).unwrap().unwrap();
+            assert_eq!(chunk.get_block(glm::vec3(7, 0, 19)), Some(id("stone")));
+            assert_eq!(chunk.get_block(glm::vec3(3, 5, 7)), Some(id("grass_block")));
+            assert_eq!(chunk.get_block(glm::vec3(1, 6, 1)), Some(id("oak_log")));
+            assert_eq!(chunk.get_block(glm::vec3(2, 6, 1)), Some(BlockId::AIR));
+
+            let chunk = storage.load_chunk(glm::vec3(0, 40, 60)).unwrap().unwrap();
+            assert_eq!(chunk.blocks().palette(), &[id("snow")]);
+
+            assert!(storage.load_chunk(glm::vec3(40, 0, 0)).unwrap().is_none());
+        }
+
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[4..8], VERSION.to_le_bytes());
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
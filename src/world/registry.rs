@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use super::block::{Block, BlockId, BlockType};
+
+/// every block type gets registered once here and then gets referred to by its `BlockId`
+#[derive(Debug, Clone)]
+pub struct BlockRegistry {
+    blocks: Vec<Block>,
+    ids: HashMap<String, BlockId>
+}
+
+impl BlockRegistry {
+    /// creates a registry that only has air in it (as `BlockId::AIR`)
+    pub fn new() -> BlockRegistry {
+        let mut registry = BlockRegistry {
+            blocks: Vec::new(),
+            ids: HashMap::new()
+        };
+
+        registry.register(Block::new("Air", "air", BlockType::Air, glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9), glm::vec2(0.9, 0.9)));
+
+        registry
+    }
+
+    /// panics if a block with the same `dev_name` was already registered
+    pub fn register(&mut self, block: Block) -> BlockId {
+        assert!(!self.ids.contains_key(&block.dev_name), "block \"{}\" is already registered", block.dev_name);
+        assert!(self.blocks.len() < u16::MAX as usize, "too many blocks registered");
+
+        let id = BlockId(self.blocks.len() as u16);
+        self.ids.insert(block.dev_name.clone(), id);
+        self.blocks.push(block);
+
+        id
+    }
+
+    /// panics if `id` wasnt handed out by this registry
+    pub fn get(&self, id: BlockId) -> &Block {
+        &self.blocks[id.0 as usize]
+    }
+
+    pub fn id(&self, dev_name: &str) -> Option<BlockId> {
+        self.ids.get(dev_name).copied()
+    }
+
+    pub fn get_by_dev_name(&self, dev_name: &str) -> Option<&Block> {
+        self.id(dev_name).map(|id| self.get(id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// never true, air is always there
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (BlockId, &Block)> {
+        self.blocks.iter().enumerate().map(|(i, block)| (BlockId(i as u16), block))
+    }
+}
+
+impl Default for BlockRegistry {
+    fn default() -> BlockRegistry {
+        BlockRegistry::new()
+    }
+}
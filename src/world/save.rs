@@ -0,0 +1,197 @@
+use std::{ffi::OsString, fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}};
+use flate2::Crc;
+use crate::json::JsonValue;
+use super::chunk::GlobalPos;
+
+/// when and how the world gets written to disk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaveSettings {
+    /// seconds between autosaves, 0 turns autosaving off
+    pub autosave_interval: f32,
+    pub save_on_exit: bool,
+    /// try to save whatever can be saved when the game panics
+    pub save_on_panic: bool
+}
+
+impl Default for SaveSettings {
+    fn default() -> SaveSettings {
+        SaveSettings {
+            autosave_interval: 60.0,
+            save_on_exit: true,
+            save_on_panic: true
+        }
+    }
+}
+
+impl SaveSettings {
+    /// every field is required, like the rest of the level file
+    pub fn from_json(json: &JsonValue) -> Result<SaveSettings, String> {
+        let field = |key: &str| json.get(key).ok_or_else(|| format!("save settings are missing \"{key}\""));
+        let bool_field = |key: &str| field(key)?.as_bool().ok_or_else(|| format!("\"{key}\" should be a bool"));
+
+        let autosave_interval = field("autosave_interval")?.as_f64()
+            .filter(|interval| interval.is_finite() && *interval >= 0.0)
+            .ok_or_else(|| "\"autosave_interval\" should be a number of seconds, 0 turns autosaving off".to_string())?;
+
+        Ok(SaveSettings {
+            autosave_interval: autosave_interval as f32,
+            save_on_exit: bool_field("save_on_exit")?,
+            save_on_panic: bool_field("save_on_panic")?
+        })
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        JsonValue::Object(vec![
+            ("autosave_interval".to_string(), JsonValue::Number(self.autosave_interval as f64)),
+            ("save_on_exit".to_string(), JsonValue::Bool(self.save_on_exit)),
+            ("save_on_panic".to_string(), JsonValue::Bool(self.save_on_panic))
+        ])
+    }
+}
+
+/// writes everything into `<path>.tmp` first and then renames it over `path`,
+/// so `path` is always either the old or the new version and never half written
+pub fn write_atomic(path: impl AsRef<Path>, bytes: &[u8]) -> io::Result<()> {
+    let temp_path = temp_path(path.as_ref());
+
+    let mut file = File::create(&temp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(temp_path, path)
+}
+
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut temp_path = OsString::from(path.as_os_str());
+    temp_path.push(".tmp");
+
+    PathBuf::from(temp_path)
+}
+
+/// every chunk a save is about to write into the region files
+///
+/// its written (atomically) before the region files are touched and deleted once theyre all done,
+/// so if its still there at startup the last save got interrupted and can just be done again
+#[derive(Debug, Clone, PartialEq)]
+pub struct Journal {
+    /// (chunk position, encoded chunk)
+    pub entries: Vec<(GlobalPos, Vec<u8>)>
+}
+
+impl Journal {
+    const MAGIC: [u8; 4] = *b"RCJN";
+    const VERSION: u32 = 1;
+
+    /// magic, version, entry count, the entries and then a crc of everything before it
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Journal::MAGIC.to_vec();
+        bytes.extend_from_slice(&Journal::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+        for (position, chunk) in &self.entries {
+            for axis in position.iter() {
+                bytes.extend_from_slice(&axis.to_le_bytes());
+            }
+            bytes.extend_from_slice(&(chunk.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(chunk);
+        }
+
+        let mut crc = Crc::new();
+        crc.update(&bytes);
+        bytes.extend_from_slice(&crc.sum().to_le_bytes());
+
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Journal, String> {
+        if bytes.len() < 16 || bytes[0..4] != Journal::MAGIC {
+            return Err("not a save journal".to_string());
+        }
+
+        let (contents, checksum) = bytes.split_at(bytes.len() - 4);
+        let mut crc = Crc::new();
+        crc.update(contents);
+        if crc.sum().to_le_bytes() != checksum {
+            return Err("checksum doesnt match".to_string());
+        }
+
+        let read_u32 = |offset: usize| contents.get(offset..offset + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap())).ok_or_else(|| "journal ends too early".to_string());
+
+        let version = read_u32(4)?;
+        if version != Journal::VERSION {
+            return Err(format!("unsupported journal version {version}"));
+        }
+
+        let count = read_u32(8)?;
+        let mut offset = 12;
+        let mut entries = Vec::new();
+
+        for _ in 0..count {
+            let position = glm::vec3(read_u32(offset)? as i32, read_u32(offset + 4)? as i32, read_u32(offset + 8)? as i32);
+            let len = read_u32(offset + 12)? as usize;
+            offset += 16;
+
+            let chunk = contents.get(offset..offset + len).ok_or_else(|| "journal ends too early".to_string())?;
+            entries.push((position, chunk.to_vec()));
+            offset += len;
+        }
+
+        if offset != contents.len() {
+            return Err("journal has bytes left over".to_string());
+        }
+
+        Ok(Journal {
+            entries
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::json::JsonValue;
+    use super::{Journal, SaveSettings};
+
+    #[test]
+    fn save_settings_round_trip() {
+        let settings = SaveSettings { autosave_interval: 12.5, save_on_exit: false, save_on_panic: true };
+        assert_eq!(SaveSettings::from_json(&settings.to_json()), Ok(settings));
+
+        for invalid in [
+            r#"{ "autosave_interval": -1, "save_on_exit": true, "save_on_panic": true }"#,
+            r#"{ "autosave_interval": 60, "save_on_exit": 1, "save_on_panic": true }"#,
+            r#"{ "autosave_interval": 60, "save_on_exit": true }"#
+        ] {
+            assert!(SaveSettings::from_json(&JsonValue::parse(invalid).unwrap()).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn journal_round_trips() {
+        let journal = Journal {
+            entries: vec![(glm::vec3(0, 20, -40), vec![1, 2, 3]), (glm::vec3(-20, 0, 0), Vec::new())]
+        };
+        assert_eq!(Journal::decode(&journal.encode()).unwrap(), journal);
+
+        let empty = Journal { entries: Vec::new() };
+        assert_eq!(Journal::decode(&empty.encode()).unwrap(), empty);
+    }
+
+    #[test]
+    fn broken_journals_dont_decode() {
+        let bytes = Journal { entries: vec![(glm::vec3(0, 20, -40), vec![1, 2, 3])] }.encode();
+
+        for i in 0..bytes.len() {
+            let mut corrupt = bytes.clone();
+            corrupt[i] ^= 0x10;
+            assert!(Journal::decode(&corrupt).is_err(), "flipped byte {i}");
+        }
+
+        let mut wrong_crc = bytes.clone();
+        *wrong_crc.last_mut().unwrap() ^= 1;
+        assert_eq!(Journal::decode(&wrong_crc), Err("checksum doesnt match".to_string()));
+
+        assert!(Journal::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Journal::decode(&[]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use std::{any::Any, collections::HashMap, panic::{self, AssertUnwindSafe}, sync::{Arc, Mutex, MutexGuard, PoisonError, atomic::{AtomicBool, Ordering}, mpsc::{self, Receiver, SyncSender}}, thread::{self, JoinHandle}};
+use super::{block::BlockId, chunk::{Chunk, GlobalPos}, mesher::{self, ChunkMesh, MeshingMode}, registry::BlockRegistry, region::RegionStorage, generation::TerrainGenerator, ChunkPos};
+
+/// everything the workers share with the main thread
+pub struct WorkerContext {
+    pub registry: BlockRegistry,
+    pub generator: Box<dyn TerrainGenerator>,
+    pub seed: u32,
+    pub storage: Mutex<RegionStorage>
+}
+
+impl WorkerContext {
+    /// still hands out the storage if a worker panicked while it had it, a save only
+    /// touches the region files after its journal is complete so theres nothing half done in there
+    pub fn storage(&self) -> MutexGuard<'_, RegionStorage> {
+        self.storage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+pub enum Job {
+    /// reads the chunk from disk or generates and decorates it, skipped if `cancelled` is set by then
+    Load {
+        key: ChunkPos,
+        cancelled: Arc<AtomicBool>
+    },
+    /// `neighbours` are indexed like `mesher::Neighbours`
+    Mesh {
+        key: ChunkPos,
+        version: u64,
+        mode: MeshingMode,
+        ambient_occlusion: bool,
+        chunk: Box<Chunk>,
+        neighbours: Box<[Option<Chunk>; 27]>
+    }
+}
+
+pub enum JobResult {
+    Loaded {
+        key: ChunkPos,
+        chunk: Chunk,
+        /// whatever `TerrainGenerator::decorate` gave back, for chunks from disk only the blocks
+        /// that go into chunks which were never saved, the saved ones already have them
+        decorations: Vec<(GlobalPos, BlockId)>
+    },
+    Meshed {
+        key: ChunkPos,
+        version: u64,
+        mesh: ChunkMesh
+    },
+    /// the job panicked, the main thread should panic with this too so the world still gets saved
+    Panicked(Box<dyn Any + Send>)
+}
+
+/// threads that load and mesh chunks in the background
+///
+/// both queues are bounded, when the job queue is full `submit` gives the job back
+/// and when nobody takes the results the workers wait
+pub struct ChunkWorkers {
+    jobs: Option<SyncSender<Job>>,
+    results: Receiver<JobResult>,
+    threads: Vec<JoinHandle<()>>
+}
+
+impl ChunkWorkers {
+    pub fn new(thread_count: usize, queue_size: usize, context: Arc<WorkerContext>) -> ChunkWorkers {
+        let (job_sender, job_receiver) = mpsc::sync_channel::<Job>(queue_size);
+        let (result_sender, result_receiver) = mpsc::sync_channel(queue_size);
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        let threads = (0..thread_count.max(1)).map(|i| {
+            let job_receiver = job_receiver.clone();
+            let result_sender = result_sender.clone();
+            let context = context.clone();
+
+            thread::Builder::new().name(format!("chunk worker {i}")).spawn(move || loop {
+                // the lock is only held while waiting for a job, not while working on it
+                let job = match job_receiver.lock().unwrap().recv() {
+                    Ok(job) => job,
+                    Err(_) => return
+                };
+
+                let result = match panic::catch_unwind(AssertUnwindSafe(|| run_job(job, &context))) {
+                    Ok(result) => result,
+                    Err(payload) => Some(JobResult::Panicked(payload))
+                };
+
+                if let Some(result) = result {
+                    if result_sender.send(result).is_err() {
+                        return;
+                    }
+                }
+            }).unwrap()
+        }).collect();
+
+        ChunkWorkers {
+            jobs: Some(job_sender),
+            results: result_receiver,
+            threads
+        }
+    }
+
+    /// as many threads as there are cores, leaving one for the main thread
+    pub fn default_thread_count() -> usize {
+        thread::available_parallelism().map_or(1, |count| count.get().saturating_sub(1).max(1))
+    }
+
+    /// false if the queue is full, the job should be tried again in a later frame
+    pub fn submit(&self, job: Job) -> bool {
+        self.jobs.as_ref().unwrap().try_send(job).is_ok()
+    }
+
+    /// a finished job if there is one, never waits
+    pub fn receive(&self) -> Option<JobResult> {
+        self.results.try_recv().ok()
+    }
+}
+
+impl Drop for ChunkWorkers {
+    fn drop(&mut self) {
+        // closing the job queue stops the workers once theyre done with whats left,
+        // the results have to be taken out in the meantime or they would wait forever
+        self.jobs = None;
+        while self.results.recv().is_ok() {}
+
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn run_job(job: Job, context: &WorkerContext) -> Option<JobResult> {
+    match job {
+        Job::Load { key, cancelled } => {
+            if cancelled.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let chunk_position = key * Chunk::SIZE as i32;
+            // only locked for reading the bytes, the other workers shouldnt wait for this one to decompress
+            let stored = context.storage().read_chunk(chunk_position);
+            let stored = match stored.and_then(|stored| stored.map(|stored| stored.decode()).transpose()) {
+                Ok(stored) => stored,
+                // better to lose the edits to the chunk than the whole world
+                Err(e) => {
+                    println!("Failed to load chunk at {:?}, generating it again: {e}", chunk_position);
+                    None
+                }
+            };
+
+            // the saved blocks could have been edited, so the features come from the chunk as it was generated
+            let generated = context.generator.generate(chunk_position, context.seed);
+            let mut decorations = context.generator.decorate(&generated, context.seed);
+            // features are only placed once, the saved chunks got them before they were saved
+            if stored.is_some() {
+                let mut storage = context.storage();
+                let mut on_disk = HashMap::new();
+                decorations.retain(|(global_pos, _)| {
+                    let chunk_position = Chunk::locate(*global_pos).0;
+                    // if its unreadable the blocks still only go where nothing else is
+                    !*on_disk.entry(chunk_position).or_insert_with(|| storage.contains(chunk_position).unwrap_or(false))
+                });
+            }
+
+            Some(JobResult::Loaded { key, chunk: stored.unwrap_or(generated), decorations })
+        }
+        Job::Mesh { key, version, mode, ambient_occlusion, chunk, neighbours } => {
+            let mesh = mesher::build_mesh(&chunk, &neighbours.each_ref().map(|c| c.as_ref()), &context.registry, mode, ambient_occlusion);
+
+            Some(JobResult::Meshed { key, version, mesh })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf, sync::{Arc, Mutex, atomic::AtomicBool}, thread};
+    use crate::world::{block::BlockId, chunk::GlobalPos, id_map::BlockIdMap, registry::BlockRegistry, region::{self, RegionStorage}};
+    use super::{ChunkWorkers, Job, JobResult, WorkerContext};
+
+    /// also gives back the directory of the storage so it can be deleted after
+    fn context(name: &str, generator: fn(GlobalPos, u32) -> BlockId) -> (Arc<WorkerContext>, PathBuf) {
+        let registry = BlockRegistry::new();
+        let block_ids = BlockIdMap::new(Vec::new(), &registry);
+        let directory = region::tests::test_directory(name);
+
+        let context = Arc::new(WorkerContext {
+            storage: Mutex::new(RegionStorage::open(&directory, block_ids).unwrap()),
+            registry,
+            generator: Box::new(generator),
+            seed: 0
+        });
+
+        (context, directory)
+    }
+
+    fn receive(workers: &ChunkWorkers) -> JobResult {
+        loop {
+            if let Some(result) = workers.receive() {
+                return result;
+            }
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn panics_get_passed_on() {
+        let (context, directory) = context("worker-panics", |pos, _| if pos.y < 0 { panic!("generator broke") } else { BlockId::AIR });
+        let workers = ChunkWorkers::new(2, 4, context);
+
+        assert!(workers.submit(Job::Load { key: glm::vec3(0, -1, 0), cancelled: Arc::new(AtomicBool::new(false)) }));
+        let JobResult::Panicked(payload) = receive(&workers) else {
+            panic!("job should have panicked");
+        };
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"generator broke"));
+
+        // the worker is still there for the next job
+        for _ in 0..2 {
+            assert!(workers.submit(Job::Load { key: glm::vec3(0, 1, 0), cancelled: Arc::new(AtomicBool::new(false)) }));
+            assert!(matches!(receive(&workers), JobResult::Loaded { .. }));
+        }
+
+        drop(workers);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn storage_survives_a_panic() {
+        let (context, directory) = context("storage-survives-a-panic", |_, _| BlockId::AIR);
+
+        let panicking_context = context.clone();
+        assert!(thread::spawn(move || {
+            let _storage = panicking_context.storage();
+            panic!("panicked while saving");
+        }).join().is_err());
+
+        assert!(context.storage.is_poisoned());
+        assert!(context.storage().load_chunk(glm::vec3(0, 0, 0)).unwrap().is_none());
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}